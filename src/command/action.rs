@@ -1,7 +1,10 @@
 use clap::builder::Str;
 
 use crate::{
-    command::application::{CLEAR, CREATE, DELETE, EXIT, HISTORY, LIST, LS, Q, QUEUE, TEST},
+    command::application::{
+        CLEAR, CREATE, DELETE, EXIT, HEATMAP, HISTORY, INTERRUPT, LIST, LS, NOTE, PAUSE, Q, QUEUE,
+        RATE, RELOAD_CONFIG, RESUME, SHUTDOWN, STATS, STATUS, TASK, TEST,
+    },
     error::ParseError,
 };
 
@@ -14,6 +17,17 @@ pub enum ActionType {
     Exit,
     Clear,
     History,
+    Status,
+    Shutdown,
+    ReloadConfig,
+    Task,
+    Interrupt,
+    Pause,
+    Resume,
+    Note,
+    Rate,
+    Stats,
+    Heatmap,
 }
 
 impl ActionType {
@@ -27,6 +41,17 @@ impl ActionType {
             EXIT => Ok(ActionType::Exit),
             CLEAR => Ok(ActionType::Clear),
             HISTORY => Ok(ActionType::History),
+            STATUS => Ok(ActionType::Status),
+            SHUTDOWN => Ok(ActionType::Shutdown),
+            RELOAD_CONFIG => Ok(ActionType::ReloadConfig),
+            TASK => Ok(ActionType::Task),
+            INTERRUPT => Ok(ActionType::Interrupt),
+            PAUSE => Ok(ActionType::Pause),
+            RESUME => Ok(ActionType::Resume),
+            NOTE => Ok(ActionType::Note),
+            RATE => Ok(ActionType::Rate),
+            STATS => Ok(ActionType::Stats),
+            HEATMAP => Ok(ActionType::Heatmap),
             _ => Err(ParseError::new(format!(
                 "failed to parse str ({}) to ActionType",
                 s
@@ -46,6 +71,17 @@ impl From<ActionType> for String {
             ActionType::Exit => String::from(EXIT),
             ActionType::Clear => String::from(CLEAR),
             ActionType::History => String::from(HISTORY),
+            ActionType::Status => String::from(STATUS),
+            ActionType::Shutdown => String::from(SHUTDOWN),
+            ActionType::ReloadConfig => String::from(RELOAD_CONFIG),
+            ActionType::Task => String::from(TASK),
+            ActionType::Interrupt => String::from(INTERRUPT),
+            ActionType::Pause => String::from(PAUSE),
+            ActionType::Resume => String::from(RESUME),
+            ActionType::Note => String::from(NOTE),
+            ActionType::Rate => String::from(RATE),
+            ActionType::Stats => String::from(STATS),
+            ActionType::Heatmap => String::from(HEATMAP),
         }
     }
 }
@@ -61,6 +97,17 @@ impl From<ActionType> for Str {
             ActionType::Exit => EXIT.into(),
             ActionType::Clear => CLEAR.into(),
             ActionType::History => HISTORY.into(),
+            ActionType::Status => STATUS.into(),
+            ActionType::Shutdown => SHUTDOWN.into(),
+            ActionType::ReloadConfig => RELOAD_CONFIG.into(),
+            ActionType::Task => TASK.into(),
+            ActionType::Interrupt => INTERRUPT.into(),
+            ActionType::Pause => PAUSE.into(),
+            ActionType::Resume => RESUME.into(),
+            ActionType::Note => NOTE.into(),
+            ActionType::Rate => RATE.into(),
+            ActionType::Stats => STATS.into(),
+            ActionType::Heatmap => HEATMAP.into(),
         }
     }
 }
@@ -1,8 +1,12 @@
-use clap::{Arg, ArgMatches, Command};
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
 use std::sync::Arc;
 
 use crate::command::action::ActionType;
+use crate::command::util;
 use crate::configuration::Configuration;
+use crate::daemon::DaemonOptions;
+use crate::goal::DailyGoal;
 
 pub const CREATE: &str = "create";
 pub const QUEUE: &str = "queue";
@@ -14,6 +18,20 @@ pub const TEST: &str = "test";
 pub const EXIT: &str = "exit";
 pub const CLEAR: &str = "clear";
 pub const HISTORY: &str = "history";
+pub const STATUS: &str = "status";
+pub const SHUTDOWN: &str = "shutdown";
+pub const RELOAD_CONFIG: &str = "reload-config";
+pub const TASK: &str = "task";
+pub const INTERRUPT: &str = "interrupt";
+pub const PAUSE: &str = "pause";
+pub const RESUME: &str = "resume";
+pub const NOTE: &str = "note";
+pub const RATE: &str = "rate";
+pub const STATS: &str = "stats";
+pub const HEATMAP: &str = "heatmap";
+pub const WATCH: &str = "watch";
+pub const DAEMON: &str = "daemon";
+pub const CONFIG: &str = "config";
 
 const AUTHOR: &str = "Young";
 const BINARY_NAME: &str = "pomodoro";
@@ -23,8 +41,12 @@ pub const DEFAULT_BREAK_TIME: u16 = 5;
 
 pub enum CommandType {
     StartUp(Arc<Configuration>),
+    Daemon(Arc<Configuration>, DaemonOptions),
+    SystemdUnit(ArgMatches),
     UdsClient(ArgMatches),
     AutoComplete(ArgMatches),
+    Watch,
+    Config(ArgMatches),
 }
 
 pub fn get_start_and_uds_client_command() -> Command {
@@ -33,18 +55,62 @@ pub fn get_start_and_uds_client_command() -> Command {
         .author(AUTHOR)
         .about("start up application with config or run command using uds client")
         .args_conflicts_with_subcommands(true)
+        .arg(get_config_arg())
         .arg(
-            Arg::new("config")
-                .help("Read configuration json file from this path")
+            Arg::new("output")
+                .help("The output format of the command result")
                 .num_args(1)
-                .short('c')
-                .long("config"),
+                .short('o')
+                .long("output")
+                .value_parser(["table", "json", "csv"])
+                .default_value("table")
+                .global(true),
+        )
+        .arg(
+            Arg::new("socket")
+                .long_help(
+                    "The server socket path, the client sockets are created next to it.
+Falls back to `POMODORO_SOCKET`, then to a socket in `$TMPDIR/$USER`.
+",
+                )
+                .num_args(1)
+                .long("socket")
+                .global(true),
+        )
+        .arg(
+            Arg::new("instance")
+                .help("Run a separate timer with its own socket and data, e.g. `--instance work`")
+                .num_args(1)
+                .long("instance")
+                .global(true),
+        )
+        .arg(
+            Arg::new("spawn-server")
+                .long_help(
+                    "Start the server as a daemon when it is not running, then run the command.
+Can be enabled for every command by setting `POMODORO_SPAWN_SERVER=1`.
+",
+                )
+                .long("spawn-server")
+                .num_args(0)
+                .global(true),
+        )
+        .arg(
+            Arg::new("server-config")
+                .help("The configuration file of the server started by `--spawn-server`")
+                .num_args(1)
+                .long("server-config")
+                .global(true),
         )
         .subcommands({
             let mut cmd = get_common_subcommands();
+            cmd.push(Command::new(WATCH).about("print server events as json lines"));
+            cmd.push(get_daemon_subcommand());
+            cmd.push(get_config_subcommand());
             cmd.push(
                 Command::new("completion")
                     .about("generate completions for shells")
+                    .arg(get_config_arg())
                     .arg(Arg::new("shell").value_parser([
                         "fish",
                         "zsh",
@@ -57,6 +123,58 @@ pub fn get_start_and_uds_client_command() -> Command {
         })
 }
 
+fn get_config_arg() -> Arg {
+    Arg::new("config")
+        .long_help(
+            "Read configuration file (json, toml or yaml, by extension) from this path.
+Without it `$XDG_CONFIG_HOME/pomodoro/config.{toml,yaml,json}` is used when it exists.
+",
+        )
+        .num_args(1)
+        .short('c')
+        .long("config")
+}
+
+fn get_config_subcommand() -> Command {
+    Command::new(CONFIG)
+        .about("inspect the configuration file without starting the server")
+        .subcommand_required(true)
+        .arg(get_config_arg().global(true))
+        .subcommand(Command::new("path").about("print the path of the configuration file in use"))
+        .subcommand(
+            Command::new("show")
+                .about("print the effective configuration, secrets are hidden, and its report"),
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("check the configuration file, exits with an error when it is invalid"),
+        )
+}
+
+fn get_daemon_subcommand() -> Command {
+    Command::new(DAEMON)
+        .about("start up application as a background service without the prompt")
+        .arg(get_config_arg())
+        .arg(
+            Arg::new("log-file")
+                .help("Append logs to this file instead of stderr")
+                .num_args(1)
+                .long("log-file"),
+        )
+        .arg(
+            Arg::new("pid-file")
+                .help("Write the process id to this file")
+                .num_args(1)
+                .long("pid-file"),
+        )
+        .arg(
+            Arg::new("systemd-unit")
+                .help("Print a systemd user unit running the daemon with the given flags and exit")
+                .num_args(0)
+                .long("systemd-unit"),
+        )
+}
+
 pub fn get_main_command() -> Command {
     Command::new(BINARY_NAME)
         .no_binary_name(true)
@@ -78,7 +196,7 @@ fn get_common_subcommands() -> Vec<Command> {
             let cmd = Command::new(ActionType::Create)
                 .alias("c")
                 .about("create the notification");
-            add_args_for_create_subcommand(cmd)
+            add_args_for_start_time(add_args_for_create_subcommand(cmd))
         },
         {
             let cmd = Command::new(ActionType::Queue)
@@ -91,9 +209,9 @@ fn get_common_subcommands() -> Vec<Command> {
             .about("delete a notification")
             .arg(
                 Arg::new("id")
-                    .help("The ID of notification to delete")
+                    .help("The ID (or a unique prefix of it) of notification to delete")
                     .num_args(1)
-                    .conflicts_with("all")
+                    .value_parser(util::parse_id_prefix)
                     .short('i')
                     .long("id"),
             )
@@ -102,35 +220,287 @@ fn get_common_subcommands() -> Vec<Command> {
                     .help("The flag to delete all notifications")
                     .short('a')
                     .num_args(0)
-                    .long("all"),
+                    .long("all")
+                    .action(ArgAction::SetTrue),
+            )
+            .group(ArgGroup::new("target").args(["id", "all"]).required(true)),
+        add_args_for_label_filter(
+            Command::new(ActionType::List)
+                .alias(LS)
+                .about("list notifications")
+                .arg(
+                    Arg::new("percentage")
+                        .short('p')
+                        .help("show work time completion percentage")
+                        .num_args(0),
+                ),
+        ),
+        add_args_for_label_filter(
+            Command::new(ActionType::History)
+                .about("show archived notifications")
+                .arg(
+                    Arg::new("clear")
+                        .help("The flag to delete all notifications from history")
+                        .short('c')
+                        .num_args(0)
+                        .long("clear"),
+                ),
+        ),
+        Command::new(ActionType::Status)
+            .about("show the current pomodoro in a machine-readable form")
+            .arg(
+                Arg::new("format")
+                    .help("The output format")
+                    .short('f')
+                    .long("format")
+                    .num_args(1)
+                    .value_parser(["json", "plain", "template"])
+                    .default_value("plain"),
+            )
+            .arg(
+                Arg::new("template")
+                    .long_help(
+                        "The template used with `--format template`.
+Available placeholders: {phase}, {remaining}, {remaining_seconds}, {description}, {id}, {completed}, {queued}, {interruptions}, {goal}
+",
+                    )
+                    .short('t')
+                    .long("template")
+                    .num_args(1),
             ),
-        Command::new(ActionType::List)
-            .alias(LS)
-            .about("list notifications")
+        Command::new(ActionType::Test).about("test notification"),
+        Command::new(ActionType::Shutdown)
+            .about("stop the server, in-flight notifications resume on next start up"),
+        Command::new(ActionType::ReloadConfig)
+            .about("re-read the configuration file used by new notifications"),
+        Command::new(ActionType::Pause)
+            .about("stop the timers of running pomodoros until they are resumed")
             .arg(
-                Arg::new("percentage")
-                    .short('p')
-                    .help("show work time completion percentage")
+                Arg::new("id")
+                    .help("The ID (or a unique prefix of it) of notification, all running ones by default")
+                    .value_parser(util::parse_id_prefix),
+            ),
+        Command::new(ActionType::Resume)
+            .about("restart the timers of paused pomodoros where they stopped")
+            .arg(
+                Arg::new("id")
+                    .help("The ID (or a unique prefix of it) of notification, all paused ones by default")
+                    .value_parser(util::parse_id_prefix),
+            ),
+        Command::new(ActionType::Interrupt)
+            .about("record an interruption of the pomodoro in its work phase")
+            .arg(
+                Arg::new("external")
+                    .help("Someone or something else interrupted, instead of yourself")
+                    .short('e')
+                    .long("external")
                     .num_args(0),
+            )
+            .arg(
+                Arg::new("note")
+                    .help("What interrupted the pomodoro")
+                    .num_args(1..),
             ),
-        Command::new(ActionType::History)
-            .about("show archived notifications")
+        Command::new(ActionType::Note)
+            .about("write a note about a pomodoro whose work time is over")
+            .arg(get_notification_id_arg())
             .arg(
-                Arg::new("clear")
-                    .help("The flag to delete all notifications from history")
-                    .short('c')
-                    .num_args(0)
-                    .long("clear"),
+                Arg::new("note")
+                    .help("The note, replaces the previous one")
+                    .required(true)
+                    .num_args(1..),
+            ),
+        Command::new(ActionType::Rate)
+            .about("rate the focus of a pomodoro whose work time is over")
+            .arg(get_notification_id_arg())
+            .arg(
+                Arg::new("rating")
+                    .help("The focus rating, from 1 to 5")
+                    .required(true)
+                    .value_parser(clap::value_parser!(u8).range(1..=5)),
+            ),
+        get_task_subcommand(),
+        get_stats_subcommand(),
+        Command::new(ActionType::Heatmap)
+            .about("show focus minutes per day and the streaks of days meeting the goal")
+            .arg(
+                Arg::new("weeks")
+                    .help("The number of weeks shown, 26 by default")
+                    .short('w')
+                    .long("weeks")
+                    .num_args(1)
+                    .value_parser(clap::value_parser!(u16).range(1..=53)),
+            )
+            .arg(
+                Arg::new("goal")
+                    .help("The daily goal, pomodoros like `4` or focus time like `2h`")
+                    .short('g')
+                    .long("goal")
+                    .num_args(1)
+                    .value_parser(DailyGoal::parse),
             ),
-        Command::new(ActionType::Test).about("test notification"),
     ]
 }
 
+fn get_notification_id_arg() -> Arg {
+    Arg::new("id")
+        .help("The ID of the notification, or its first characters")
+        .required(true)
+        .value_parser(util::parse_id_prefix)
+}
+
+// stats covers today unless another range is given
+fn get_stats_subcommand() -> Command {
+    let flag = |name: &'static str, help: &'static str| {
+        Arg::new(name)
+            .help(help)
+            .long(name)
+            .num_args(0)
+            .group("range")
+    };
+    let date_arg = |name: &'static str, help: &'static str| {
+        Arg::new(name)
+            .help(help)
+            .long(name)
+            .num_args(1)
+            .value_parser(util::parse_date)
+            .value_name("DATE")
+    };
+
+    Command::new(ActionType::Stats)
+        .about("summarize the pomodoros of a day, week, month or date range")
+        .arg(flag("day", "Today, the default"))
+        .arg(flag("week", "This week, from monday"))
+        .arg(flag("month", "This month"))
+        .arg(date_arg("from", "The first day, e.g. `2024-03-01`").group("range"))
+        .arg(date_arg("to", "The last day, today by default").requires("from"))
+}
+
+fn get_task_subcommand() -> Command {
+    let id_arg = || {
+        Arg::new("id")
+            .help("The ID of the task")
+            .required(true)
+            .value_parser(clap::value_parser!(u64))
+    };
+    let estimate_parser = || clap::value_parser!(u16).range(1..);
+
+    Command::new(ActionType::Task)
+        .about("plan tasks and link pomodoros to them with `create --task <id>`")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("add")
+                .about("add a task")
+                .arg(
+                    Arg::new("estimate")
+                        .help("The estimated number of pomodoros")
+                        .short('e')
+                        .long("estimate")
+                        .num_args(1)
+                        .value_parser(estimate_parser()),
+                )
+                .arg(
+                    Arg::new("title")
+                        .help("The title of the task")
+                        .required(true)
+                        .num_args(1..),
+                ),
+        )
+        .subcommand(
+            Command::new("ls")
+                .alias("list")
+                .about("list open tasks with estimated and actual pomodoros")
+                .arg(
+                    Arg::new("all")
+                        .help("Also list done tasks")
+                        .short('a')
+                        .long("all")
+                        .num_args(0),
+                ),
+        )
+        .subcommand(
+            Command::new("done")
+                .about("mark a task as done")
+                .arg(id_arg()),
+        )
+        .subcommand(
+            Command::new("estimate")
+                .about("set the estimated number of pomodoros of a task")
+                .arg(id_arg())
+                .arg(
+                    Arg::new("pomodoros")
+                        .help("The estimated number of pomodoros")
+                        .required(true)
+                        .value_parser(estimate_parser()),
+                ),
+        )
+}
+
+// a created notification starts right away unless it is scheduled
+fn add_args_for_start_time(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("at")
+                .long_help(
+                    "Start at this local time, e.g. `14:00`. A time already passed today is tomorrow.
+",
+                )
+                .long("at")
+                .num_args(1)
+                .value_name("TIME")
+                .conflicts_with("in"),
+        )
+        .arg(
+            Arg::new("in")
+                .help("Start after this duration, e.g. `20m` or `1h30m`")
+                .long("in")
+                .num_args(1)
+                .value_name("DURATION"),
+        )
+}
+
+// list and history show only the notifications matching both filters
+fn add_args_for_label_filter(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("tag")
+                .help("Show only notifications with this tag")
+                .short('t')
+                .long("tag")
+                .num_args(1)
+                .value_parser(util::parse_label)
+                .value_name("TAG"),
+        )
+        .arg(
+            Arg::new("project")
+                .help("Show only notifications of this project")
+                .long("project")
+                .num_args(1)
+                .value_parser(util::parse_label)
+                .value_name("NAME"),
+        )
+}
+
+// with_preset_names lets the generated completion offer the configured preset names
+pub fn with_preset_names(command: Command, names: Vec<String>) -> Command {
+    if names.is_empty() {
+        return command;
+    }
+
+    [CREATE, QUEUE].into_iter().fold(command, |command, name| {
+        command.mut_subcommand(name, |cmd| {
+            cmd.mut_arg("preset", |arg| {
+                arg.value_parser(PossibleValuesParser::new(names.clone()))
+            })
+        })
+    })
+}
+
 pub(crate) fn add_args_for_create_subcommand(command: Command) -> Command {
     command
         .arg(
             Arg::new("work")
-                .long_help("The focus time in minutes.
+                .long_help("The focus time, in minutes or like `1h30m`, `45m` and `120s`.
 If no value is passed, the work time is obtained from `work_time_default_value` in the given configuration file.
 And if no configuration file is passed or `work_time_default_value` is not present, then 25 is used as the work time.
 ")
@@ -139,7 +509,7 @@ And if no configuration file is passed or `work_time_default_value` is not prese
         )
         .arg(
             Arg::new("break")
-                .long_help("The break time in minutes.
+                .long_help("The break time, in minutes or like `1h30m`, `45m` and `120s`.
 If no value is passed, the break time is obtained from `break_time_default_value` in the given configuration file.
 And if no configuration file is passed or `break_time_default_value` is not present, then 5 is used as the break time.
 ")
@@ -155,10 +525,47 @@ If no configuration file is passed or the keys are not present, then 25 minutes
 ")
                 .conflicts_with("work")
                 .conflicts_with("break")
+                .conflicts_with("preset")
                 .short('d')
                 .long("default")
                 .num_args(0),
         )
+        .arg(
+            Arg::new("preset")
+                .long_help("The name of a preset in the `presets` of the given configuration file.
+The work and break times are taken from the preset, `-w` and `-b` still override them.
+")
+                .short('p')
+                .long("preset")
+                .num_args(1)
+                .value_parser(util::parse_preset_name)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("tag")
+                .help("Tag the notification, can be given several times")
+                .short('t')
+                .long("tag")
+                .action(ArgAction::Append)
+                .value_parser(util::parse_label)
+                .value_name("TAG"),
+        )
+        .arg(
+            Arg::new("project")
+                .help("The project the notification is worked for")
+                .long("project")
+                .num_args(1)
+                .value_parser(util::parse_label)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("task")
+                .help("Link the notification to the task with this ID, see `task ls`")
+                .long("task")
+                .num_args(1)
+                .value_parser(clap::value_parser!(u64))
+                .value_name("ID"),
+        )
         .arg(
             Arg::new("description")
                 .long_help("Sets the description for the notification")
@@ -175,6 +582,7 @@ mod tests {
     use clap::{Arg, Command};
 
     use crate::command::application::get_common_subcommands;
+    use crate::goal::DailyGoal;
 
     use super::{add_args_for_create_subcommand, get_main_command};
 
@@ -183,10 +591,18 @@ mod tests {
         let uds_cmd = get_start_and_uds_client_command();
         let completion_cmd = Command::new("completion")
             .about("generate completions for shells")
+            .arg(super::get_config_arg())
             .arg(Arg::new("shell").value_parser(["fish", "zsh", "bash", "elvish", "powershell"]));
 
+        let watch_cmd = Command::new("watch").about("print server events as json lines");
+        let daemon_cmd = super::get_daemon_subcommand();
+        let config_cmd = super::get_config_subcommand();
+
         let uds_sub_cmds = uds_cmd.get_subcommands().collect::<Vec<&Command>>();
         let mut main_sub_cmds = get_common_subcommands();
+        main_sub_cmds.push(watch_cmd);
+        main_sub_cmds.push(daemon_cmd);
+        main_sub_cmds.push(config_cmd);
         main_sub_cmds.push(completion_cmd);
 
         assert_eq!(uds_cmd.get_name(), BINARY_NAME);
@@ -237,12 +653,141 @@ mod tests {
         let cmd = get_main_command();
         let matches = cmd.try_get_matches_from("d -a".split_whitespace());
         assert!(matches.is_ok());
+
+        let matches = get_main_command()
+            .try_get_matches_from("delete -i 3F2A".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<String>("id").unwrap(), "3f2a");
+        assert!(!sub_matches.get_flag("all"));
+
+        // deleting needs a target, and only one
+        assert!(get_main_command().try_get_matches_from(["delete"]).is_err());
+        assert!(get_main_command()
+            .try_get_matches_from("delete -i 3f2a -a".split_whitespace())
+            .is_err());
+        assert!(get_main_command()
+            .try_get_matches_from("delete -i 12xz".split_whitespace())
+            .is_err());
+    }
+
+    #[test]
+    fn test_status_command() {
+        let cmd = get_main_command();
+        let matches = cmd
+            .try_get_matches_from("status -f json".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<String>("format").unwrap(), "json");
+
+        let cmd = get_main_command();
+        let matches = cmd.try_get_matches_from("status -f yaml".split_whitespace());
+        assert!(matches.is_err());
+    }
+
+    #[test]
+    fn test_output_flag() {
+        let cmd = get_start_and_uds_client_command();
+        let matches = cmd
+            .try_get_matches_from("pomodoro list --output json".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<String>("output").unwrap(), "json");
+
+        let cmd = get_start_and_uds_client_command();
+        let matches = cmd
+            .try_get_matches_from("pomodoro history".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<String>("output").unwrap(), "table");
+
+        let cmd = get_start_and_uds_client_command();
+        let matches = cmd.try_get_matches_from("pomodoro list -o xml".split_whitespace());
+        assert!(matches.is_err());
+    }
+
+    #[test]
+    fn test_spawn_server_flag() {
+        let cmd = get_start_and_uds_client_command();
+        let matches = cmd
+            .try_get_matches_from(
+                "pomodoro create -d --spawn-server --server-config config.json".split_whitespace(),
+            )
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert!(sub_matches.get_flag("spawn-server"));
+        assert_eq!(
+            sub_matches.get_one::<String>("server-config").unwrap(),
+            "config.json"
+        );
+    }
+
+    #[test]
+    fn test_instance_flags() {
+        // without subcommand the server starts up with them
+        let cmd = get_start_and_uds_client_command();
+        let matches = cmd
+            .try_get_matches_from("pomodoro --instance work --socket a.sock".split_whitespace())
+            .unwrap();
+        assert_eq!(matches.get_one::<String>("instance").unwrap(), "work");
+        assert_eq!(matches.get_one::<String>("socket").unwrap(), "a.sock");
+
+        let cmd = get_start_and_uds_client_command();
+        let matches = cmd
+            .try_get_matches_from("pomodoro list --instance work".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<String>("instance").unwrap(), "work");
+    }
+
+    #[test]
+    fn test_daemon_command() {
+        let cmd = get_start_and_uds_client_command();
+        let matches = cmd
+            .try_get_matches_from(
+                "pomodoro daemon -c config.json --log-file pomodoro.log".split_whitespace(),
+            )
+            .unwrap();
+        let sub_matches = matches.subcommand_matches("daemon").unwrap();
+        assert_eq!(
+            sub_matches.get_one::<String>("config").unwrap(),
+            "config.json"
+        );
+        assert_eq!(
+            sub_matches.get_one::<String>("log-file").unwrap(),
+            "pomodoro.log"
+        );
+        assert!(!sub_matches.get_flag("systemd-unit"));
+    }
+
+    #[test]
+    fn test_config_command() {
+        for input in [
+            "pomodoro config show -c config.toml",
+            "pomodoro config -c config.toml show",
+        ] {
+            let matches = get_start_and_uds_client_command()
+                .try_get_matches_from(input.split_whitespace())
+                .unwrap();
+            let sub_matches = matches.subcommand_matches("config").unwrap();
+            let (name, leaf_matches) = sub_matches.subcommand().unwrap();
+            assert_eq!(name, "show");
+            assert_eq!(
+                leaf_matches.get_one::<String>("config").unwrap(),
+                "config.toml"
+            );
+        }
+
+        // one of path, show or validate is required
+        assert!(get_start_and_uds_client_command()
+            .try_get_matches_from("pomodoro config".split_whitespace())
+            .is_err());
     }
 
     #[test]
     fn test_get_common_subcommands() {
         let subcommands = get_common_subcommands();
-        assert_eq!(subcommands.len(), 6);
+        assert_eq!(subcommands.len(), 17);
     }
 
     #[test]
@@ -283,5 +828,181 @@ mod tests {
         assert!(matches.get_one::<String>("work").is_none());
         assert_eq!(matches.get_flag("default"), false);
         assert!(matches.contains_id("default"));
+
+        // test preset, work still can be given
+        let cmd = Command::new("myapp");
+        let matches = add_args_for_create_subcommand(cmd)
+            .get_matches_from("myapp -p deep -w 40".split_whitespace());
+        assert_eq!(matches.get_one::<String>("preset").unwrap(), "deep");
+        assert_eq!(matches.get_one::<String>("work").unwrap(), "40");
+
+        let cmd = Command::new("myapp");
+        let result = add_args_for_create_subcommand(cmd)
+            .try_get_matches_from("myapp -p deep -d".split_whitespace());
+        assert!(result.is_err());
+
+        // test tags and project
+        let cmd = Command::new("myapp");
+        let matches = add_args_for_create_subcommand(cmd)
+            .get_matches_from("myapp -t client --tag review --project billing".split_whitespace());
+        let (tags, project) = crate::command::util::parse_labels(&matches);
+        assert_eq!(tags, vec!["client", "review"]);
+        assert_eq!(project.as_deref(), Some("billing"));
+    }
+
+    #[test]
+    fn test_list_label_filter() {
+        let cmd = get_main_command();
+        let matches = cmd
+            .try_get_matches_from("ls --tag client --project billing -p".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        let (tag, project) = crate::command::util::parse_label_filter(sub_matches);
+        assert_eq!(tag.as_deref(), Some("client"));
+        assert_eq!(project.as_deref(), Some("billing"));
+        assert!(sub_matches.get_flag("percentage"));
+    }
+
+    #[test]
+    fn test_interrupt_command() {
+        let matches = get_main_command()
+            .try_get_matches_from("interrupt -e phone call".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert!(sub_matches.get_flag("external"));
+        assert_eq!(
+            crate::command::util::parse_note(sub_matches)
+                .unwrap()
+                .as_deref(),
+            Some("phone call")
+        );
+
+        let matches = get_main_command()
+            .try_get_matches_from("interrupt".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert!(!sub_matches.get_flag("external"));
+        assert!(crate::command::util::parse_note(sub_matches)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_note_and_rate_commands() {
+        let matches = get_main_command()
+            .try_get_matches_from("note 1b4e28ba deep focus, no slack".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<String>("id").unwrap(), "1b4e28ba");
+        assert_eq!(
+            crate::command::util::parse_note(sub_matches)
+                .unwrap()
+                .as_deref(),
+            Some("deep focus, no slack")
+        );
+
+        let matches = get_main_command()
+            .try_get_matches_from("rate 1b4e28ba 4".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<u8>("rating"), Some(&4));
+
+        for input in [
+            "rate 1b4e28ba 6",
+            "rate 1b4e28ba 0",
+            "rate xyz 3",
+            "note 1b4e28ba",
+        ] {
+            let result = get_main_command().try_get_matches_from(input.split_whitespace());
+            assert!(result.is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_stats_command() {
+        for input in [
+            "stats",
+            "stats --week",
+            "stats --month",
+            "stats --from 2024-03-01",
+            "stats --from 2024-03-01 --to 2024-03-10",
+        ] {
+            let result = get_main_command().try_get_matches_from(input.split_whitespace());
+            assert!(result.is_ok(), "{}", input);
+        }
+
+        for input in [
+            "stats --day --week",
+            "stats --week --from 2024-03-01",
+            "stats --to 2024-03-10",
+            "stats --from 03/01",
+        ] {
+            let result = get_main_command().try_get_matches_from(input.split_whitespace());
+            assert!(result.is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_heatmap_command() {
+        let matches = get_main_command()
+            .try_get_matches_from("heatmap -w 12 --goal 2h".split_whitespace())
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<u16>("weeks"), Some(&12));
+        assert_eq!(
+            sub_matches.get_one::<DailyGoal>("goal"),
+            Some(&DailyGoal::Minutes(120))
+        );
+
+        for input in ["heatmap -w 0", "heatmap -w 54", "heatmap --goal 0"] {
+            let result = get_main_command().try_get_matches_from(input.split_whitespace());
+            assert!(result.is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_task_command() {
+        let matches = get_main_command()
+            .try_get_matches_from("task add -e 3 write the report".split_whitespace())
+            .unwrap();
+        let (_, task_matches) = matches.subcommand().unwrap();
+        let (name, add_matches) = task_matches.subcommand().unwrap();
+        assert_eq!(name, "add");
+        assert_eq!(add_matches.get_one::<u16>("estimate"), Some(&3));
+        let title = add_matches
+            .get_many::<String>("title")
+            .unwrap()
+            .cloned()
+            .collect::<Vec<String>>();
+        assert_eq!(title.join(" "), "write the report");
+
+        let result =
+            get_main_command().try_get_matches_from("task estimate 1 0".split_whitespace());
+        assert!(result.is_err());
+
+        let matches = get_main_command()
+            .try_get_matches_from("create --task 2".split_whitespace())
+            .unwrap();
+        let (_, create_matches) = matches.subcommand().unwrap();
+        assert_eq!(create_matches.get_one::<u64>("task"), Some(&2));
+    }
+
+    #[test]
+    fn test_with_preset_names() {
+        let cmd = super::with_preset_names(
+            get_main_command(),
+            vec![String::from("deep"), String::from("standard")],
+        );
+        let create = cmd.find_subcommand(super::CREATE).unwrap();
+        let preset = create
+            .get_arguments()
+            .find(|arg| arg.get_id() == "preset")
+            .unwrap();
+        let values = preset
+            .get_possible_values()
+            .iter()
+            .map(|value| value.get_name().to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(values, vec!["deep", "standard"]);
     }
 }
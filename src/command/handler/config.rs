@@ -0,0 +1,95 @@
+use clap::ArgMatches;
+use std::result;
+
+use crate::configuration::{
+    get_candidate_files, get_config_dir, get_configuration_path, load_configuration,
+    read_configuration_file, Configuration, ConfigurationFormat,
+};
+use crate::error::ConfigurationError;
+use crate::report::generate_configuration_report;
+
+type HandleConfigResult = result::Result<(), ConfigurationError>;
+
+// handle runs `config path|show|validate` locally, the server is not involved
+pub fn handle(matches: &ArgMatches) -> HandleConfigResult {
+    let (name, sub_matches) = match matches.subcommand() {
+        Some(subcommand) => subcommand,
+        None => return Ok(()),
+    };
+
+    match name {
+        "path" => handle_path(sub_matches),
+        "show" => handle_show(sub_matches),
+        "validate" => handle_validate(sub_matches),
+        _ => Ok(()),
+    }
+}
+
+fn handle_path(matches: &ArgMatches) -> HandleConfigResult {
+    match get_configuration_path(matches)? {
+        Some(path) => {
+            println!("{}", path.display());
+            Ok(())
+        }
+        None => {
+            eprintln!("No configuration file found, looked for");
+            if let Some(dir) = get_config_dir() {
+                for candidate in get_candidate_files(&dir) {
+                    eprintln!("  {}", candidate.display());
+                }
+            }
+            Err(ConfigurationError::FileNotFound)
+        }
+    }
+}
+
+// handle_show prints the configuration the server would start with, in the format of its file
+fn handle_show(matches: &ArgMatches) -> HandleConfigResult {
+    let path = get_configuration_path(matches)?;
+    let (configuration, config_error) = load_configuration(path.as_deref())?;
+
+    let format = match configuration.get_path() {
+        Some(path) if config_error.is_some() => {
+            println!("# {} failed to load, showing the defaults", path.display());
+            ConfigurationFormat::from_path(path)
+        }
+        Some(path) => {
+            println!("# {}", path.display());
+            ConfigurationFormat::from_path(path)
+        }
+        None => {
+            println!("# no configuration file is used, showing the defaults");
+            ConfigurationFormat::Json
+        }
+    };
+    println!("{}", configuration.get_redacted().serialize(format)?);
+    println!(
+        "{}",
+        generate_configuration_report(&configuration, config_error)
+    );
+
+    Ok(())
+}
+
+fn handle_validate(matches: &ArgMatches) -> HandleConfigResult {
+    let path = get_configuration_path(matches)?.ok_or(ConfigurationError::FileNotFound)?;
+
+    match read_configuration_file(&path) {
+        Ok(configuration) => {
+            println!("{}", generate_configuration_report(&configuration, None));
+            // a secret which can't be read is as bad as a missing one
+            if !configuration.get_credential_errors().is_empty() {
+                return Err(ConfigurationError::Invalid(path));
+            }
+            println!("{} is valid", path.display());
+            Ok(())
+        }
+        Err(e) => {
+            println!(
+                "{}",
+                generate_configuration_report(&Configuration::default(), Some(e))
+            );
+            Err(ConfigurationError::Invalid(path))
+        }
+    }
+}
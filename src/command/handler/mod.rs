@@ -1,2 +1,3 @@
+pub(crate) mod config;
 pub(crate) mod uds_client;
 pub(crate) mod user_input;
@@ -1,19 +1,23 @@
-use crate::ipc::{Bincodec, UdsMessage};
+use crate::ipc::{internal, Bincodec, ClientUds, UdsMessage, CHUNK};
+use chrono::Local;
 use clap::ArgMatches;
 use std::result;
 use tokio::net::UnixDatagram;
 
 use crate::command::action::ActionType;
+use crate::command::output::{self, OutputFormat};
 use crate::command::util;
 use crate::error::UdsHandlerError;
-use crate::ipc::{MessageRequest, MessageResponse};
-
-pub const BUFFER_LENGTH: usize = 100_000_000;
+use crate::goal::DailyGoal;
+use crate::heatmap::Heatmap;
+use crate::ipc::{MessageRequest, MessageResponse, TaskRequest};
+use crate::sled_databbase::NotificationSled;
+use crate::status::{Status, StatusFormat};
 
 type HandleUdsResult = result::Result<(), UdsHandlerError>;
 
 // TODO(young): handle error properly
-pub async fn handle(matches: ArgMatches, socket: UnixDatagram) -> HandleUdsResult {
+pub async fn handle(matches: ArgMatches, socket: ClientUds) -> HandleUdsResult {
     let (action_type, sub_matches) = matches
         .subcommand()
         .ok_or(UdsHandlerError::NoSubcommand)
@@ -22,14 +26,70 @@ pub async fn handle(matches: ArgMatches, socket: UnixDatagram) -> HandleUdsResul
                 .map(|s| (s, sub_matches))
                 .map_err(UdsHandlerError::ParseError)
         })?;
+    let output_format =
+        util::parse_output_format(sub_matches).map_err(UdsHandlerError::ParseError)?;
 
     match action_type {
-        ActionType::Create => handle_create(socket, sub_matches).await?,
-        ActionType::Queue => handle_queue(socket, sub_matches).await?,
-        ActionType::Delete => handle_delete(socket, sub_matches).await?,
-        ActionType::List => handle_list(socket, sub_matches).await?,
-        ActionType::Test => handle_test(socket).await?,
-        ActionType::History => handle_history(socket, sub_matches).await?,
+        ActionType::Create => handle_create(socket, sub_matches, output_format).await?,
+        ActionType::Queue => handle_queue(socket, sub_matches, output_format).await?,
+        ActionType::Delete => handle_delete(socket, sub_matches, output_format).await?,
+        ActionType::List => handle_list(socket, sub_matches, output_format).await?,
+        ActionType::Test => handle_test(socket, output_format).await?,
+        ActionType::History => handle_history(socket, sub_matches, output_format).await?,
+        ActionType::Status => handle_status(socket, sub_matches, output_format).await?,
+        ActionType::Shutdown => {
+            handle_simple_request(socket, MessageRequest::Shutdown, output_format).await?
+        }
+        ActionType::ReloadConfig => {
+            handle_simple_request(socket, MessageRequest::ReloadConfig, output_format).await?
+        }
+        ActionType::Task => handle_task(socket, sub_matches, output_format).await?,
+        ActionType::Interrupt => {
+            let request = MessageRequest::Interrupt {
+                external: sub_matches.get_flag("external"),
+                note: util::parse_note(sub_matches).map_err(UdsHandlerError::ParseError)?,
+            };
+            handle_simple_request(socket, request, output_format).await?
+        }
+        ActionType::Pause => {
+            let request = MessageRequest::Pause {
+                id: sub_matches.get_one::<String>("id").cloned(),
+            };
+            handle_simple_request(socket, request, output_format).await?
+        }
+        ActionType::Resume => {
+            let request = MessageRequest::Resume {
+                id: sub_matches.get_one::<String>("id").cloned(),
+            };
+            handle_simple_request(socket, request, output_format).await?
+        }
+        ActionType::Note => {
+            // clap requires both arguments
+            let request = MessageRequest::Note {
+                id: sub_matches.get_one::<String>("id").unwrap().to_string(),
+                note: util::parse_note(sub_matches)
+                    .map_err(UdsHandlerError::ParseError)?
+                    .unwrap_or_default(),
+            };
+            handle_simple_request(socket, request, output_format).await?
+        }
+        ActionType::Rate => {
+            let request = MessageRequest::Rate {
+                id: sub_matches.get_one::<String>("id").unwrap().to_string(),
+                rating: *sub_matches.get_one::<u8>("rating").unwrap(),
+            };
+            handle_simple_request(socket, request, output_format).await?
+        }
+        ActionType::Stats => {
+            let range = util::parse_stats_range(sub_matches, Local::now().date_naive())
+                .map_err(UdsHandlerError::ParseError)?;
+            let request = MessageRequest::Stats {
+                from: range.from.to_string(),
+                to: range.to.to_string(),
+            };
+            handle_simple_request(socket, request, output_format).await?
+        }
+        ActionType::Heatmap => handle_heatmap(socket, sub_matches, output_format).await?,
         ActionType::Exit | ActionType::Clear => {
             info!("Exit or Clear is not supported action for unix domain client")
         }
@@ -38,9 +98,18 @@ pub async fn handle(matches: ArgMatches, socket: UnixDatagram) -> HandleUdsResul
     Ok(())
 }
 
-async fn handle_create(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleUdsResult {
-    let (work_time, break_time, description) =
+async fn handle_create(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    let (work_time, break_time, description, preset) =
         util::parse_work_and_break_time(sub_matches, None).map_err(UdsHandlerError::ParseError)?;
+    let (tags, project) = util::parse_labels(sub_matches);
+    // the time of day is resolved here, in the timezone of the user
+    let start_at = util::parse_start_at(sub_matches, &Local::now())
+        .map_err(UdsHandlerError::ParseError)?
+        .map(|start_at| start_at.timestamp());
 
     socket
         .send(
@@ -48,6 +117,11 @@ async fn handle_create(socket: UnixDatagram, sub_matches: &ArgMatches) -> Handle
                 work: work_time,
                 r#break: break_time,
                 desciption: description,
+                preset,
+                tags,
+                project,
+                task: sub_matches.get_one::<u64>("task").copied(),
+                start_at,
             })
             .encode()
             .map_err(UdsHandlerError::EncodeFailed)?
@@ -56,14 +130,19 @@ async fn handle_create(socket: UnixDatagram, sub_matches: &ArgMatches) -> Handle
         .await
         .map_err(UdsHandlerError::SocketError)?;
 
-    decode_and_print_message(socket).await?;
+    decode_and_print_message(socket, output_format).await?;
 
     Ok(())
 }
 
-async fn handle_queue(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleUdsResult {
-    let (work_time, break_time, description) =
+async fn handle_queue(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    let (work_time, break_time, description, preset) =
         util::parse_work_and_break_time(sub_matches, None).map_err(UdsHandlerError::ParseError)?;
+    let (tags, project) = util::parse_labels(sub_matches);
 
     debug!("handle_queue");
     socket
@@ -72,6 +151,10 @@ async fn handle_queue(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleU
                 work: work_time,
                 r#break: break_time,
                 desciption: description,
+                preset,
+                tags,
+                project,
+                task: sub_matches.get_one::<u64>("task").copied(),
             })
             .encode()
             .map_err(UdsHandlerError::EncodeFailed)?
@@ -80,19 +163,27 @@ async fn handle_queue(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleU
         .await
         .map_err(UdsHandlerError::SocketError)?;
 
-    decode_and_print_message(socket).await?;
+    decode_and_print_message(socket, output_format).await?;
 
     Ok(())
 }
 
-async fn handle_delete(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleUdsResult {
+async fn handle_delete(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    // clap requires either the id or the all flag
     let (id, all) = if sub_matches.contains_id("id") {
         (
-            util::parse_arg::<u16>(sub_matches, "id").map_err(UdsHandlerError::ParseError)?,
+            Some(
+                util::parse_arg::<String>(sub_matches, "id")
+                    .map_err(UdsHandlerError::ParseError)?,
+            ),
             false,
         )
     } else {
-        (0, true)
+        (None, true)
     };
 
     socket
@@ -105,17 +196,42 @@ async fn handle_delete(socket: UnixDatagram, sub_matches: &ArgMatches) -> Handle
         .await
         .map_err(UdsHandlerError::SocketError)?;
 
-    decode_and_print_message(socket).await?;
+    decode_and_print_message(socket, output_format).await?;
 
     Ok(())
 }
 
-async fn handle_list(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleUdsResult {
+async fn handle_list(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
     let show_percentage = sub_matches.get_flag("percentage");
+    let (tag, project) = util::parse_label_filter(sub_matches);
+
+    socket
+        .send(
+            UdsMessage::Public(MessageRequest::List {
+                show_percentage,
+                tag,
+                project,
+            })
+            .encode()
+            .map_err(UdsHandlerError::EncodeFailed)?
+            .as_slice(),
+        )
+        .await
+        .map_err(UdsHandlerError::SocketError)?;
+
+    decode_and_print_message(socket, output_format).await?;
+
+    Ok(())
+}
 
+async fn handle_test(socket: ClientUds, output_format: OutputFormat) -> HandleUdsResult {
     socket
         .send(
-            UdsMessage::Public(MessageRequest::List { show_percentage })
+            UdsMessage::Public(MessageRequest::Test)
                 .encode()
                 .map_err(UdsHandlerError::EncodeFailed)?
                 .as_slice(),
@@ -123,15 +239,51 @@ async fn handle_list(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleUd
         .await
         .map_err(UdsHandlerError::SocketError)?;
 
-    decode_and_print_message(socket).await?;
+    decode_and_print_message(socket, output_format).await?;
 
     Ok(())
 }
 
-async fn handle_test(socket: UnixDatagram) -> HandleUdsResult {
+async fn handle_history(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    let should_clear = sub_matches.get_flag("clear");
+    let (tag, project) = util::parse_label_filter(sub_matches);
+
     socket
         .send(
-            UdsMessage::Public(MessageRequest::Test)
+            UdsMessage::Public(MessageRequest::History {
+                should_clear,
+                tag,
+                project,
+            })
+            .encode()
+            .map_err(UdsHandlerError::EncodeFailed)?
+            .as_slice(),
+        )
+        .await
+        .map_err(UdsHandlerError::SocketError)?;
+
+    decode_and_print_message(socket, output_format).await?;
+
+    Ok(())
+}
+
+async fn handle_status(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    let format = match output_format {
+        OutputFormat::Json => StatusFormat::Json,
+        _ => util::parse_status_format(sub_matches).map_err(UdsHandlerError::ParseError)?,
+    };
+
+    socket
+        .send(
+            UdsMessage::Public(MessageRequest::Status)
                 .encode()
                 .map_err(UdsHandlerError::EncodeFailed)?
                 .as_slice(),
@@ -139,17 +291,94 @@ async fn handle_test(socket: UnixDatagram) -> HandleUdsResult {
         .await
         .map_err(UdsHandlerError::SocketError)?;
 
-    decode_and_print_message(socket).await?;
+    let response = decode_message(&socket, &mut [0u8; CHUNK]).await?;
+    // server answers with json, anything else is an error message worth printing as is
+    match response
+        .get_body()
+        .iter()
+        .find_map(|m| Status::from_json(m))
+    {
+        Some(status) => println!("{}", status.render(&format)),
+        None => response.print(),
+    }
 
     Ok(())
 }
 
-async fn handle_history(socket: UnixDatagram, sub_matches: &ArgMatches) -> HandleUdsResult {
-    let should_clear = sub_matches.get_flag("clear");
+async fn handle_heatmap(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    let request = MessageRequest::Heatmap {
+        weeks: sub_matches.get_one::<u16>("weeks").copied(),
+        goal: sub_matches
+            .get_one::<DailyGoal>("goal")
+            .map(|goal| goal.to_arg()),
+    };
+    if output_format != OutputFormat::Table {
+        return handle_simple_request(socket, request, output_format).await;
+    }
+
+    socket
+        .send(
+            UdsMessage::Public(request)
+                .encode()
+                .map_err(UdsHandlerError::EncodeFailed)?
+                .as_slice(),
+        )
+        .await
+        .map_err(UdsHandlerError::SocketError)?;
+
+    // the map is rendered here, the server may have no terminal to color it for
+    let response = decode_message(&socket, &mut [0u8; CHUNK]).await?;
+    match response.get_data().and_then(Heatmap::from_json) {
+        Some(heatmap) => println!("{}", heatmap.render()),
+        None => response.print(),
+    }
+
+    Ok(())
+}
 
+async fn handle_task(
+    socket: ClientUds,
+    sub_matches: &ArgMatches,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    // the task subcommands require their arguments, clap already checked them
+    let request = match sub_matches.subcommand() {
+        Some(("add", matches)) => TaskRequest::Add {
+            title: matches
+                .get_many::<String>("title")
+                .map(|title| title.cloned().collect::<Vec<String>>().join(" "))
+                .unwrap_or_default(),
+            estimate: matches.get_one::<u16>("estimate").copied(),
+        },
+        Some(("ls", matches)) => TaskRequest::List {
+            all: matches.get_flag("all"),
+        },
+        Some(("done", matches)) => TaskRequest::Done {
+            id: *matches.get_one::<u64>("id").unwrap(),
+        },
+        Some(("estimate", matches)) => TaskRequest::Estimate {
+            id: *matches.get_one::<u64>("id").unwrap(),
+            estimate: *matches.get_one::<u16>("pomodoros").unwrap(),
+        },
+        _ => return Err(UdsHandlerError::NoSubcommand),
+    };
+
+    handle_simple_request(socket, MessageRequest::Task(request), output_format).await
+}
+
+// handle_simple_request sends a request and prints the response
+async fn handle_simple_request(
+    socket: ClientUds,
+    request: MessageRequest,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
     socket
         .send(
-            UdsMessage::Public(MessageRequest::History { should_clear })
+            UdsMessage::Public(request)
                 .encode()
                 .map_err(UdsHandlerError::EncodeFailed)?
                 .as_slice(),
@@ -157,20 +386,71 @@ async fn handle_history(socket: UnixDatagram, sub_matches: &ArgMatches) -> Handl
         .await
         .map_err(UdsHandlerError::SocketError)?;
 
-    decode_and_print_message(socket).await?;
+    decode_and_print_message(socket, output_format).await?;
 
     Ok(())
 }
 
-async fn decode_and_print_message(socket: UnixDatagram) -> HandleUdsResult {
+// handle_watch prints every event pushed by the server until the process is stopped
+pub async fn handle_watch(socket: ClientUds) -> HandleUdsResult {
+    socket
+        .send(
+            UdsMessage::Internal(internal::Message::Subscribe)
+                .encode()
+                .map_err(UdsHandlerError::EncodeFailed)?
+                .as_slice(),
+        )
+        .await
+        .map_err(UdsHandlerError::SocketError)?;
+
+    // events keep coming, one buffer serves them all
+    let mut buf = [0u8; CHUNK];
+    loop {
+        decode_message(&socket, &mut buf).await?.print();
+    }
+}
+
+async fn decode_and_print_message(
+    socket: ClientUds,
+    output_format: OutputFormat,
+) -> HandleUdsResult {
+    let response = decode_message(&socket, &mut [0u8; CHUNK]).await?;
+
+    match (output_format, response.get_data()) {
+        (OutputFormat::Json, Some(data)) => println!("{}", data),
+        (OutputFormat::Json, None) => println!(
+            "{}",
+            serde_json::to_string(response.get_body()).unwrap_or_default()
+        ),
+        (OutputFormat::Csv, Some(data)) => match parse_notifications(data) {
+            Some(notifications) => println!("{}", output::notifications_to_csv(&notifications)),
+            None => response.print(),
+        },
+        _ => response.print(),
+    }
+
+    Ok(())
+}
+
+// data is either a list of notifications (list, history) or a single one (create, queue)
+fn parse_notifications(data: &str) -> Option<Vec<NotificationSled>> {
+    serde_json::from_str::<Vec<NotificationSled>>(data)
+        .or_else(|_| serde_json::from_str::<NotificationSled>(data).map(|n| vec![n]))
+        .ok()
+}
+
+// decode_message receives the datagrams of one message in `buf`, at least `CHUNK` long
+async fn decode_message(
+    socket: &UnixDatagram,
+    buf: &mut [u8],
+) -> Result<MessageResponse, UdsHandlerError> {
     let mut vec = Vec::new();
     let mut total_size = 0;
 
     // TODO(young): set timeout to prevent infinite loop
     loop {
-        let mut buf = vec![0u8; BUFFER_LENGTH];
         let (size, _) = socket
-            .recv_from(&mut buf)
+            .recv_from(buf)
             .await
             .map_err(UdsHandlerError::SocketError)?;
         debug!("decode_and_print_message, size: {}", size);
@@ -189,9 +469,5 @@ async fn decode_and_print_message(socket: UnixDatagram) -> HandleUdsResult {
 
     debug!("total_size: {}", total_size);
     let dgram = &vec.as_slice()[..total_size];
-    MessageResponse::decode(dgram)
-        .map_err(UdsHandlerError::DecodeFailed)?
-        .print();
-
-    Ok(())
+    MessageResponse::decode(dgram).map_err(UdsHandlerError::DecodeFailed)
 }
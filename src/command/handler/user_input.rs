@@ -1,9 +1,7 @@
-use chrono::Utc;
+use chrono::{Duration, Local, Utc};
 use clap::error::ErrorKind;
 use clap::{ArgMatches, Command};
-use std::process;
 use std::result;
-use std::str::SplitWhitespace;
 use std::sync::Arc;
 use tabled::locator::ByColumnName;
 use tabled::object::Segment;
@@ -13,25 +11,36 @@ use tabled::{Alignment, Disable};
 use tabled::{Style, TableIteratorExt};
 
 use crate::command::output::{OutputAccumulater, OutputType};
-use crate::command::{self, action::ActionType};
+use crate::command::{self, action::ActionType, util};
+use crate::configuration::{get_configuration_diff, reload_configuration, run_blocking};
 use crate::error::UserInputHandlerError;
+use crate::event::{self, Event, EventKind, EventSender};
+use crate::goal::{DailyGoal, GoalProgress, DEFAULT_DAILY_GOAL};
+use crate::heatmap::{Heatmap, DEFAULT_HEATMAP_WEEKS};
 use crate::notification::get_new_notification_sled;
 use crate::notification::notify::notify_work;
-use crate::SledStore;
+use crate::report::generate_configuration_report;
+use crate::sled_databbase::{Interruption, InterruptionKind};
+use crate::stats::Stats;
+use crate::status::{Phase, Status};
+use crate::task::TaskProgress;
 use crate::{configuration::Configuration, ArcGlue};
-use crate::{spawn_notification, ArcTaskMap};
+use crate::{spawn_notification, ArcTaskMap, InputSource};
+use crate::{NotificationSled, SledStore};
 
 type HandleUserInputResult = result::Result<(), UserInputHandlerError>;
 
 pub async fn handle(
-    user_input: &str,
+    user_input: &[String],
+    source: &InputSource,
     notification_task_map: &ArcTaskMap,
     glue: &ArcGlue,
-    configuration: &Arc<Configuration>,
+    configuration: &mut Arc<Configuration>,
     sled_store: &SledStore,
+    events: &EventSender,
 ) -> Result<OutputAccumulater, UserInputHandlerError> {
     let command = command::get_main_command();
-    let input = user_input.split_whitespace();
+    let input = user_input.iter();
     let mut output_accumulator = OutputAccumulater::new();
 
     debug!("input: {:?}", input);
@@ -60,6 +69,7 @@ pub async fn handle(
                 notification_task_map,
                 &mut output_accumulator,
                 sled_store,
+                events,
             )
             .await?;
         }
@@ -70,16 +80,75 @@ pub async fn handle(
                 notification_task_map,
                 &mut output_accumulator,
                 &sled_store,
+                events,
             )
             .await?;
         }
-        ActionType::Delete => {}
-        ActionType::List => handle_list(sub_matches, &mut output_accumulator, sled_store).await?,
+        ActionType::Delete => handle_delete(
+            sub_matches,
+            notification_task_map,
+            &mut output_accumulator,
+            sled_store,
+            events,
+        )?,
+        ActionType::List => {
+            handle_list(
+                sub_matches,
+                configuration,
+                &mut output_accumulator,
+                sled_store,
+            )
+            .await?
+        }
         ActionType::Test => handle_test(configuration, &mut output_accumulator).await?,
         ActionType::History => {
             handle_history(sub_matches, glue, &mut output_accumulator, sled_store).await?
         }
-        ActionType::Exit => process::exit(0),
+        ActionType::Status => {
+            handle_status(
+                sub_matches,
+                source,
+                configuration,
+                &mut output_accumulator,
+                sled_store,
+            )
+            .await?
+        }
+        ActionType::Shutdown | ActionType::Exit => {
+            handle_shutdown(notification_task_map, &mut output_accumulator, sled_store).await?
+        }
+        ActionType::ReloadConfig => {
+            handle_reload_config(configuration, &mut output_accumulator).await?
+        }
+        ActionType::Task => handle_task(sub_matches, &mut output_accumulator, sled_store)?,
+        ActionType::Interrupt => {
+            handle_interrupt(sub_matches, &mut output_accumulator, sled_store)?
+        }
+        ActionType::Pause => handle_pause(
+            sub_matches,
+            notification_task_map,
+            &mut output_accumulator,
+            sled_store,
+            events,
+        )?,
+        ActionType::Resume => handle_resume(
+            sub_matches,
+            configuration,
+            notification_task_map,
+            &mut output_accumulator,
+            sled_store,
+            events,
+        )?,
+        ActionType::Note | ActionType::Rate => {
+            handle_review(sub_matches, &mut output_accumulator, sled_store)?
+        }
+        ActionType::Stats => handle_stats(sub_matches, &mut output_accumulator, sled_store)?,
+        ActionType::Heatmap => handle_heatmap(
+            sub_matches,
+            configuration,
+            &mut output_accumulator,
+            sled_store,
+        )?,
         ActionType::Clear => print!("\x1B[2J\x1B[1;1H"),
     }
 
@@ -92,27 +161,46 @@ async fn handle_create(
     notification_task_map: &ArcTaskMap,
     output_accumulator: &mut OutputAccumulater,
     sled_store: &SledStore,
+    events: &EventSender,
 ) -> HandleUserInputResult {
-    let notification_new = get_new_notification_sled(matches, Utc::now(), configuration.clone())
-        .map_err(UserInputHandlerError::NotificationError)?;
+    let start_at =
+        util::parse_start_at(matches, &Local::now()).map_err(UserInputHandlerError::ParseError)?;
+    check_task(matches, sled_store)?;
+    let notification_new = get_new_notification_sled(
+        matches,
+        start_at.unwrap_or_else(Utc::now),
+        configuration.clone(),
+    )
+    .map_err(UserInputHandlerError::NotificationError)?;
 
-    let _ = sled_store.create_notification(&notification_new);
-    let id = notification_new.get_id();
+    // stored before the timer runs, a failed write is reported to the client instead
+    let id = sled_store.create_notification(&notification_new)?;
+    publish_created(events, &notification_new);
+    output_accumulator.set_data(&notification_new);
 
     let handle = spawn_notification(
         configuration.clone(),
         notification_task_map.clone(),
         &sled_store,
+        events.clone(),
         notification_new,
     );
 
     notification_task_map.lock().unwrap().insert(id, handle);
+    let scheduled = match start_at {
+        Some(start_at) => format!(
+            ", starts at {}",
+            start_at.with_timezone(&Local).format("%F %T %z")
+        ),
+        None => String::new(),
+    };
     output_accumulator.push(
         OutputType::Println,
         format!(
-            "[{}] Notification (id: {}) created",
+            "[{}] Notification (id: {}) created{}",
             chrono::offset::Local::now(),
-            id
+            id,
+            scheduled
         ),
     );
 
@@ -125,14 +213,17 @@ async fn handle_queue(
     notification_task_map: &ArcTaskMap,
     output_accumulator: &mut OutputAccumulater,
     sled_store: &SledStore,
+    events: &EventSender,
 ) -> HandleUserInputResult {
-    let created_at = sled_store.get_time_for_queue_notification()?;
-    let notification_new = get_new_notification_sled(matches, created_at, configuration.clone())
+    check_task(matches, sled_store)?;
+    let start_at = sled_store.get_time_for_queue_notification()?;
+    let notification_new = get_new_notification_sled(matches, start_at, configuration.clone())
         .map_err(UserInputHandlerError::NotificationError)?;
 
-    let id = notification_new.get_id();
-    let _ = sled_store.create_notification(&notification_new);
+    let id = sled_store.create_notification(&notification_new)?;
     debug!("Queue notification: {:?}", notification_new);
+    publish_created(events, &notification_new);
+    output_accumulator.set_data(&notification_new);
 
     notification_task_map.lock().unwrap().insert(
         id,
@@ -140,6 +231,7 @@ async fn handle_queue(
             configuration.clone(),
             notification_task_map.clone(),
             sled_store,
+            events.clone(),
             notification_new,
         ),
     );
@@ -155,12 +247,46 @@ async fn handle_queue(
     Ok(())
 }
 
+// check_task rejects pomodoros linked to a task which does not exist or is done
+fn check_task(matches: &ArgMatches, sled_store: &SledStore) -> HandleUserInputResult {
+    let id = match matches.get_one::<u64>("task") {
+        Some(id) => *id,
+        None => return Ok(()),
+    };
+
+    match sled_store.get_task(id)? {
+        Some(task) if task.is_done() => Err(UserInputHandlerError::TaskAlreadyDone(id)),
+        Some(_) => Ok(()),
+        None => Err(UserInputHandlerError::UnknownTask(id)),
+    }
+}
+
+// publish_created also reports the start when the notification is not queued behind others,
+// queued ones are reported as started by spawn_notification
+fn publish_created(events: &EventSender, notification: &NotificationSled) {
+    event::publish(
+        events,
+        Event::new(EventKind::Created, notification, Phase::Idle),
+    );
+
+    if notification.get_start_at() <= Utc::now() {
+        event::publish(
+            events,
+            Event::new(
+                EventKind::Started,
+                notification,
+                event::get_starting_phase(notification),
+            ),
+        );
+    }
+}
+
 async fn handle_test(
     configuration: &Arc<Configuration>,
     output_accumulator: &mut OutputAccumulater,
 ) -> HandleUserInputResult {
     debug!("Message:NotificationTest called!");
-    let report = notify_work(&configuration.clone())
+    let report = notify_work(&configuration.clone(), None)
         .await
         .map_err(UserInputHandlerError::NotificationError)?;
     output_accumulator.push(OutputType::Info, format!("\n{}", report));
@@ -176,13 +302,19 @@ async fn handle_test(
 
 async fn handle_list(
     sub_matches: &ArgMatches,
+    configuration: &Arc<Configuration>,
     output_accumulator: &mut OutputAccumulater,
     sled_store: &SledStore,
 ) -> HandleUserInputResult {
     debug!("handle_list::List called!");
+    let (tag, project) = util::parse_label_filter(sub_matches);
 
     let mut main_table_sled = match sled_store.list_notifications() {
-        Ok(sleds) => sleds.table(),
+        Ok(mut sleds) => {
+            sleds.retain(|n| n.has_labels(tag.as_deref(), project.as_deref()));
+            output_accumulator.set_data(&sleds);
+            sleds.table()
+        }
         Err(e) => {
             output_accumulator.push(OutputType::Error, format!("Error: {}", e));
             return Ok(());
@@ -197,6 +329,11 @@ async fn handle_list(
         )
         .with(Modify::new(Segment::all()).with(Alignment::center()));
 
+    // notes and ratings are written after the work time, they are shown by history
+    let styled_table = styled_table
+        .with(Disable::column(ByColumnName::new("focus")))
+        .with(Disable::column(ByColumnName::new("note")));
+
     let table_sled: String = if !sub_matches.get_flag("percentage") {
         styled_table
             .with(Disable::column(ByColumnName::new("percentage")))
@@ -206,24 +343,39 @@ async fn handle_list(
     };
 
     output_accumulator.push(OutputType::Info, format!("\n{}", table_sled));
+    if let Some(goal) = configuration.get_daily_goal() {
+        let progress = GoalProgress::today(goal, &sled_store.list_all_notifications()?, Utc::now());
+        output_accumulator.push(OutputType::Println, format!("Daily goal: {}", progress));
+    }
     output_accumulator.push(OutputType::Println, String::from("List succeed"));
 
     Ok(())
 }
 
 async fn handle_history(
-    _sub_matches: &ArgMatches,
+    sub_matches: &ArgMatches,
     _glue: &ArcGlue,
     output_accumulator: &mut OutputAccumulater,
     sled_store: &SledStore,
 ) -> HandleUserInputResult {
     debug!("Message:History called!");
     debug!("Message:History done!");
+    let (tag, project) = util::parse_label_filter(sub_matches);
+
+    if sub_matches.get_flag("clear") {
+        let cleared = sled_store.clear_history(tag.as_deref(), project.as_deref())?;
+        output_accumulator.push(
+            OutputType::Println,
+            format!("Cleared {} notifications from history", cleared),
+        );
+    }
 
     let mut main_table_sled = match sled_store.list_all_notifications() {
-        Ok(sleds) => {
+        Ok(mut sleds) => {
+            sleds.retain(|n| n.has_labels(tag.as_deref(), project.as_deref()));
             let item_count = sleds.len();
             debug!("History: sled items count {}", item_count);
+            output_accumulator.set_data(&sleds);
             sleds.table()
         }
         Err(e) => {
@@ -247,10 +399,478 @@ async fn handle_history(
     Ok(())
 }
 
-// get_matches extract ArgMatches from input string
-fn get_matches(
+fn handle_interrupt(
+    sub_matches: &ArgMatches,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+) -> HandleUserInputResult {
+    let kind = if sub_matches.get_flag("external") {
+        InterruptionKind::External
+    } else {
+        InterruptionKind::Internal
+    };
+    let interruption = Interruption {
+        at: Utc::now(),
+        kind,
+        note: util::parse_note(sub_matches).map_err(UserInputHandlerError::ParseError)?,
+    };
+
+    let id = sled_store
+        .record_interruption(interruption.clone())?
+        .ok_or(UserInputHandlerError::NotWorking)?;
+    output_accumulator.set_data(&interruption);
+    output_accumulator.push(
+        OutputType::Println,
+        format!("Interruption recorded on notification (id: {})", id),
+    );
+
+    Ok(())
+}
+
+// handle_delete removes registered notifications and stops their timers
+fn handle_delete(
+    sub_matches: &ArgMatches,
+    notification_task_map: &ArcTaskMap,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+    events: &EventSender,
+) -> HandleUserInputResult {
+    // only registered notifications, archived ones are cleared by `history --clear`.
+    // clap requires either the id or the all flag
+    let notifications = filter_by_id(sub_matches, sled_store.list_notifications()?)?;
+
+    for notification in notifications.iter() {
+        if let Some(handle) = notification_task_map
+            .lock()
+            .unwrap()
+            .remove(&notification.id)
+        {
+            handle.abort();
+        }
+        sled_store.delete(notification.id)?;
+        event::publish(
+            events,
+            Event::new(EventKind::Deleted, notification, Phase::Idle),
+        );
+    }
+
+    output_accumulator.set_data(&notifications);
+    output_accumulator.push(
+        OutputType::Println,
+        format!("{} notification(s) deleted", notifications.len()),
+    );
+
+    Ok(())
+}
+
+// handle_pause stops the timers of running pomodoros, what remains of them is kept until resumed
+fn handle_pause(
+    sub_matches: &ArgMatches,
+    notification_task_map: &ArcTaskMap,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+    events: &EventSender,
+) -> HandleUserInputResult {
+    let now = Utc::now();
+    let running = sled_store
+        .list_notifications()?
+        .into_iter()
+        .filter(|n| !n.is_paused() && n.get_start_at() <= now)
+        .collect();
+    let mut notifications = filter_by_id(sub_matches, running)?;
+    if notifications.is_empty() {
+        return Err(UserInputHandlerError::NotRunning);
+    }
+
+    for notification in notifications.iter_mut() {
+        if let Some(handle) = notification_task_map
+            .lock()
+            .unwrap()
+            .remove(&notification.id)
+        {
+            handle.abort();
+        }
+        notification.paused_at = Some(now);
+        sled_store.insert(notification.id, notification)?;
+        event::publish(
+            events,
+            Event::new(EventKind::Paused, notification, Phase::Paused),
+        );
+    }
+
+    output_accumulator.set_data(&notifications);
+    output_accumulator.push(
+        OutputType::Println,
+        format!("{} notification(s) paused", notifications.len()),
+    );
+
+    Ok(())
+}
+
+// handle_resume moves the times of paused pomodoros, and of the ones queued behind them, by the
+// paused duration and restarts their timers
+fn handle_resume(
+    sub_matches: &ArgMatches,
+    configuration: &Arc<Configuration>,
+    notification_task_map: &ArcTaskMap,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+    events: &EventSender,
+) -> HandleUserInputResult {
+    let now = Utc::now();
+    let paused = sled_store
+        .list_notifications()?
+        .into_iter()
+        .filter(|n| n.is_paused())
+        .collect();
+    let mut notifications = filter_by_id(sub_matches, paused)?;
+    if notifications.is_empty() {
+        return Err(UserInputHandlerError::NotPaused);
+    }
+
+    // the pomodoros queued behind the paused ones wait for as long as they were paused
+    let queued_after = notifications.iter().map(|n| n.break_expired_at).min();
+    let paused_for = notifications
+        .iter()
+        .filter_map(|n| n.paused_at)
+        .map(|paused_at| (now - paused_at).max(Duration::zero()))
+        .max();
+    if let (Some(after), Some(by)) = (queued_after, paused_for) {
+        for queued in sled_store.postpone_queued(after, by)? {
+            if let Some(handle) = notification_task_map.lock().unwrap().remove(&queued.id) {
+                handle.abort();
+            }
+            notification_task_map.lock().unwrap().insert(
+                queued.id,
+                spawn_notification(
+                    configuration.clone(),
+                    notification_task_map.clone(),
+                    sled_store,
+                    events.clone(),
+                    queued,
+                ),
+            );
+        }
+    }
+
+    for notification in notifications.iter_mut() {
+        notification.resume(now);
+        sled_store.insert(notification.id, notification)?;
+        event::publish(
+            events,
+            Event::new(
+                EventKind::Resumed,
+                notification,
+                event::get_current_phase(notification, now),
+            ),
+        );
+        notification_task_map.lock().unwrap().insert(
+            notification.id,
+            spawn_notification(
+                configuration.clone(),
+                notification_task_map.clone(),
+                sled_store,
+                events.clone(),
+                notification.clone(),
+            ),
+        );
+    }
+
+    output_accumulator.set_data(&notifications);
+    output_accumulator.push(
+        OutputType::Println,
+        format!("{} notification(s) resumed", notifications.len()),
+    );
+
+    Ok(())
+}
+
+// filter_by_id keeps the notification whose id starts with the `id` argument, all of them without it
+fn filter_by_id(
+    sub_matches: &ArgMatches,
+    mut notifications: Vec<NotificationSled>,
+) -> Result<Vec<NotificationSled>, UserInputHandlerError> {
+    if let Some(id) = sub_matches.get_one::<String>("id") {
+        notifications.retain(|n| n.id.to_string().starts_with(id.as_str()));
+        if notifications.len() > 1 {
+            return Err(UserInputHandlerError::AmbiguousNotification(id.to_string()));
+        }
+        if notifications.is_empty() {
+            return Err(UserInputHandlerError::UnknownNotification(id.to_string()));
+        }
+    }
+
+    Ok(notifications)
+}
+
+// handle_review stores the note or the focus rating of a pomodoro whose work time is over
+fn handle_review(
+    sub_matches: &ArgMatches,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+) -> HandleUserInputResult {
+    // clap requires the id and the note or rating
+    let id = sub_matches.get_one::<String>("id").unwrap();
+    let mut found = sled_store.find_notifications(id)?;
+    if found.len() > 1 {
+        return Err(UserInputHandlerError::AmbiguousNotification(id.to_string()));
+    }
+    let mut notification = found
+        .pop()
+        .ok_or_else(|| UserInputHandlerError::UnknownNotification(id.to_string()))?;
+
+    if notification.work_expired_at > Utc::now() {
+        return Err(UserInputHandlerError::WorkNotOver(notification.id));
+    }
+
+    // `note` has no rating argument
+    let message = match sub_matches.try_get_one::<u8>("rating").ok().flatten() {
+        Some(rating) => {
+            notification.rating = Some(*rating);
+            format!("Notification (id: {}) rated {}", notification.id, rating)
+        }
+        None => {
+            notification.note =
+                util::parse_note(sub_matches).map_err(UserInputHandlerError::ParseError)?;
+            format!("Note written on notification (id: {})", notification.id)
+        }
+    };
+
+    sled_store.insert(notification.id, &notification)?;
+    output_accumulator.set_data(&notification);
+    output_accumulator.push(OutputType::Println, message);
+
+    Ok(())
+}
+
+fn handle_task(
+    sub_matches: &ArgMatches,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+) -> HandleUserInputResult {
+    let (name, matches) = sub_matches
+        .subcommand()
+        .ok_or(UserInputHandlerError::NoSubcommand)?;
+    debug!("handle_task: {}", name);
+
+    let get_task = |id: u64| {
+        sled_store
+            .get_task(id)?
+            .ok_or(UserInputHandlerError::UnknownTask(id))
+    };
+
+    let (tasks, message) = match name {
+        "add" => {
+            let title = matches
+                .get_many::<String>("title")
+                .map(|title| title.cloned().collect::<Vec<String>>().join(" "))
+                .unwrap_or_default();
+            let estimate = matches.get_one::<u16>("estimate").copied();
+            let task = sled_store.add_task(title, estimate)?;
+            let message = format!("Task (id: {}) added", task.id);
+            (vec![task], message)
+        }
+        "done" => {
+            let mut task = get_task(*matches.get_one::<u64>("id").unwrap())?;
+            if task.is_done() {
+                return Err(UserInputHandlerError::TaskAlreadyDone(task.id));
+            }
+            task.done_at = Some(Utc::now());
+            sled_store.update_task(&task)?;
+            let message = format!("Task (id: {}) done", task.id);
+            (vec![task], message)
+        }
+        "estimate" => {
+            let mut task = get_task(*matches.get_one::<u64>("id").unwrap())?;
+            task.estimate = matches.get_one::<u16>("pomodoros").copied();
+            sled_store.update_task(&task)?;
+            let message = format!("Task (id: {}) estimated", task.id);
+            (vec![task], message)
+        }
+        _ => {
+            let all = matches.get_flag("all");
+            let mut tasks = sled_store.list_tasks()?;
+            tasks.retain(|task| all || !task.is_done());
+            (tasks, String::from("Task list succeed"))
+        }
+    };
+
+    let notifications = sled_store.list_all_notifications()?;
+    let now = Utc::now();
+    let progress = tasks
+        .into_iter()
+        .map(|task| TaskProgress::new(task, &notifications, now))
+        .collect::<Vec<TaskProgress>>();
+    output_accumulator.set_data(&progress);
+
+    let table = progress
+        .table()
+        .with(
+            Style::modern()
+                .off_horizontal()
+                .horizontals([HorizontalLine::new(1, Style::modern().get_horizontal())]),
+        )
+        .with(Modify::new(Segment::all()).with(Alignment::center()))
+        .to_string();
+
+    output_accumulator.push(OutputType::Info, format!("\n{}", table));
+    output_accumulator.push(OutputType::Println, message);
+
+    Ok(())
+}
+
+async fn handle_status(
+    sub_matches: &ArgMatches,
+    source: &InputSource,
+    configuration: &Arc<Configuration>,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+) -> HandleUserInputResult {
+    debug!("handle_status called!");
+
+    let format =
+        util::parse_status_format(sub_matches).map_err(UserInputHandlerError::ParseError)?;
+
+    let now = Utc::now();
+    let status = match sled_store.list_all_notifications() {
+        Ok(sleds) => Status {
+            goal: configuration
+                .get_daily_goal()
+                .map(|goal| GoalProgress::today(goal, &sleds, now)),
+            ..Status::new(&sleds, now)
+        },
+        Err(e) => {
+            output_accumulator.push(OutputType::Error, format!("Error: {}", e));
+            return Ok(());
+        }
+    };
+
+    // status bars poll it over the socket and http, only a status typed in is printed
+    let output_type = match source {
+        InputSource::StandardInput => OutputType::Println,
+        _ => OutputType::Response,
+    };
+    output_accumulator.set_data(&status);
+    output_accumulator.push(output_type, status.render(&format));
+
+    Ok(())
+}
+
+fn handle_stats(
+    sub_matches: &ArgMatches,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+) -> HandleUserInputResult {
+    let range = util::parse_stats_range(sub_matches, Local::now().date_naive())
+        .map_err(UserInputHandlerError::ParseError)?;
+    let stats = Stats::new(&sled_store.list_all_notifications()?, range, Utc::now());
+
+    output_accumulator.set_data(&stats);
+    output_accumulator.push(OutputType::Println, stats.render());
+
+    Ok(())
+}
+
+fn handle_heatmap(
+    sub_matches: &ArgMatches,
+    configuration: &Arc<Configuration>,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+) -> HandleUserInputResult {
+    let weeks = sub_matches
+        .get_one::<u16>("weeks")
+        .copied()
+        .unwrap_or(DEFAULT_HEATMAP_WEEKS);
+    let goal = sub_matches
+        .get_one::<DailyGoal>("goal")
+        .copied()
+        .or_else(|| configuration.get_daily_goal())
+        .unwrap_or(DEFAULT_DAILY_GOAL);
+    let heatmap = Heatmap::new(
+        &sled_store.list_all_notifications()?,
+        weeks,
+        goal,
+        Utc::now(),
+    );
+
+    output_accumulator.set_data(&heatmap);
+    output_accumulator.push(OutputType::Println, heatmap.render());
+
+    Ok(())
+}
+
+// handle_shutdown persists the store and asks the server to stop once the response is sent.
+// Running notifications stay in the store and are spawned again on the next start up
+async fn handle_shutdown(
+    notification_task_map: &ArcTaskMap,
+    output_accumulator: &mut OutputAccumulater,
+    sled_store: &SledStore,
+) -> HandleUserInputResult {
+    debug!("handle_shutdown called!");
+
+    let in_flight = sled_store.list_notifications()?;
+    for notification in in_flight.iter() {
+        info!(
+            "in-flight notification (id: {}) work expires at {}, break expires at {}",
+            notification.get_id(),
+            notification.work_expired_at,
+            notification.break_expired_at
+        );
+    }
+    sled_store.flush()?;
+
+    for (_, handle) in notification_task_map.lock().unwrap().drain() {
+        handle.abort();
+    }
+
+    output_accumulator.request_shutdown();
+    output_accumulator.push(
+        OutputType::Println,
+        format!(
+            "Server is shutting down, {} in-flight notification(s) saved",
+            in_flight.len()
+        ),
+    );
+
+    Ok(())
+}
+
+// handle_reload_config swaps the configuration, notifications already running keep the old one
+async fn handle_reload_config(
+    configuration: &mut Arc<Configuration>,
+    output_accumulator: &mut OutputAccumulater,
+) -> HandleUserInputResult {
+    debug!("handle_reload_config called!");
+
+    // on error the current configuration stays in use
+    let current = configuration.clone();
+    let reloaded = run_blocking(move || reload_configuration(&current))
+        .await
+        .map_err(UserInputHandlerError::ConfigurationError)?;
+    let diff = get_configuration_diff(configuration, &reloaded);
+    let report = generate_configuration_report(&reloaded, None);
+    *configuration = Arc::new(reloaded);
+
+    match diff.is_empty() {
+        true => output_accumulator.push(OutputType::Info, String::from("No changes")),
+        false => output_accumulator.push(
+            OutputType::Info,
+            format!("Changes:\n  {}", diff.join("\n  ")),
+        ),
+    }
+    output_accumulator.push(OutputType::Info, format!("\n{}", report));
+    output_accumulator.push(
+        OutputType::Println,
+        String::from("Configuration reloaded, the http api keeps its start up settings"),
+    );
+
+    Ok(())
+}
+
+// get_matches extract ArgMatches from input arguments
+fn get_matches<'a>(
     command: Command,
-    input: SplitWhitespace,
+    input: impl IntoIterator<Item = &'a String>,
     output_accumulator: &mut OutputAccumulater,
 ) -> Result<Option<ArgMatches>, UserInputHandlerError> {
     match command.try_get_matches_from(input) {
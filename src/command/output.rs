@@ -1,17 +1,37 @@
+use crate::sled_databbase::{InterruptionKind, NotificationSled};
+
 pub enum OutputType {
     Info,
     Error,
     Print,
     Println,
+    // only sent back to the client, the server terminal stays quiet
+    Response,
+}
+
+/// How the uds client renders a response, selected with the global `--output` flag
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
 }
 
 pub struct OutputAccumulater {
     body: Vec<String>,
+    // structured result as json, rendered by the uds client for `--output json|csv`
+    data: Option<String>,
+    // set by `shutdown`, the server stops once the response is sent
+    shutdown_requested: bool,
 }
 
 impl OutputAccumulater {
     pub fn new() -> Self {
-        OutputAccumulater { body: Vec::new() }
+        OutputAccumulater {
+            body: Vec::new(),
+            data: None,
+            shutdown_requested: false,
+        }
     }
 
     pub fn push(&mut self, r#type: OutputType, message: String) {
@@ -28,6 +48,7 @@ impl OutputAccumulater {
             OutputType::Println => {
                 println!("{}", message);
             }
+            OutputType::Response => {}
         }
 
         self.body.push(message);
@@ -37,4 +58,118 @@ impl OutputAccumulater {
     pub fn take_body(&mut self) -> Vec<String> {
         std::mem::take(&mut self.body)
     }
+
+    pub fn set_data<T: serde::Serialize + ?Sized>(&mut self, data: &T) {
+        match serde_json::to_string(data) {
+            Ok(json) => self.data = Some(json),
+            Err(e) => debug!("failed to serialize output data: {}", e),
+        }
+    }
+
+    // take_data extract data. After call, OutputAccumulater has no data
+    pub fn take_data(&mut self) -> Option<String> {
+        self.data.take()
+    }
+
+    pub fn request_shutdown(&mut self) {
+        self.shutdown_requested = true;
+    }
+
+    pub fn is_shutdown_requested(&self) -> bool {
+        self.shutdown_requested
+    }
+}
+
+pub fn notifications_to_csv(notifications: &[NotificationSled]) -> String {
+    let mut lines = vec![String::from(
+        "id,description,work_seconds,break_seconds,created_at,start_at,work_expired_at,break_expired_at,preset,project,tags,task,internal_interruptions,external_interruptions,rating,note",
+    )];
+
+    for n in notifications {
+        let fields = [
+            n.id.to_string(),
+            n.description.to_string(),
+            n.work_seconds.to_string(),
+            n.break_seconds.to_string(),
+            n.created_at.to_rfc3339(),
+            n.get_start_at().to_rfc3339(),
+            n.work_expired_at.to_rfc3339(),
+            n.break_expired_at.to_rfc3339(),
+            n.preset.clone().unwrap_or_default(),
+            n.project.clone().unwrap_or_default(),
+            n.tags.join(" "),
+            n.task.map(|task| task.to_string()).unwrap_or_default(),
+            n.count_interruptions(InterruptionKind::Internal)
+                .to_string(),
+            n.count_interruptions(InterruptionKind::External)
+                .to_string(),
+            n.rating
+                .map(|rating| rating.to_string())
+                .unwrap_or_default(),
+            n.note.clone().unwrap_or_default(),
+        ];
+
+        lines.push(
+            fields
+                .iter()
+                .map(|field| escape_csv_field(field))
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+    }
+
+    lines.join("\n")
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{escape_csv_field, notifications_to_csv, OutputAccumulater, OutputType};
+    use crate::sled_databbase::NotificationSled;
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("A pomodoro"), "A pomodoro");
+        assert_eq!(escape_csv_field("write, review"), "\"write, review\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_notifications_to_csv() {
+        let mut notification =
+            NotificationSled::new(String::from("write, review"), 25 * 60, 5 * 60, Utc::now());
+        notification.project = Some(String::from("billing"));
+        notification.tags = vec![String::from("client"), String::from("review")];
+        let csv = notifications_to_csv(&[notification]);
+
+        let lines = csv.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("id,description,work_seconds"));
+        assert!(lines[1].contains(",\"write, review\",1500,300,"));
+        assert!(lines[1].ends_with(",billing,client review,,0,0,,"));
+    }
+
+    #[test]
+    fn test_output_accumulator_data() {
+        let mut output_accumulator = OutputAccumulater::new();
+        output_accumulator.push(OutputType::Info, String::from("message"));
+        assert!(output_accumulator.take_data().is_none());
+
+        output_accumulator.set_data(&vec![1, 2]);
+        assert_eq!(output_accumulator.take_data().as_deref(), Some("[1,2]"));
+        assert!(output_accumulator.take_data().is_none());
+        assert_eq!(
+            output_accumulator.take_body(),
+            vec![String::from("message")]
+        );
+    }
 }
@@ -1,35 +1,67 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
 use clap::ArgMatches;
 use clap_complete::Shell;
 use std::io::{self, Write};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use crate::command::output::OutputFormat;
 use crate::command::{DEFAULT_BREAK_TIME, DEFAULT_WORK_TIME};
 use crate::configuration::Configuration;
 use crate::error::ParseError;
+use crate::stats::StatsRange;
+use crate::status::{StatusFormat, DEFAULT_STATUS_TEMPLATE};
 
+// the daemon has no terminal to prompt on
+static PROMPT_ENABLED: AtomicBool = AtomicBool::new(true);
+
+// work seconds, break seconds, description and preset name
+type CreateArgs = (Option<u32>, Option<u32>, Option<String>, Option<String>);
+
+// parse_work_and_break_time returns the create arguments.
+// Given a configuration, a preset replaces the default times and `-w`/`-b` still override it
 pub fn parse_work_and_break_time(
     matches: &ArgMatches,
     configuration: Option<&Arc<Configuration>>,
-) -> Result<(Option<u16>, Option<u16>, Option<String>), ParseError> {
+) -> Result<CreateArgs, ParseError> {
+    let preset_name = matches.get_one::<String>("preset").map(|s| s.to_string());
+
     if let Some(conf) = configuration {
-        let mut work_time = match conf.get_work_time() {
-            Some(work_time) => work_time,
-            None => DEFAULT_WORK_TIME,
+        let preset = match &preset_name {
+            Some(name) => Some(conf.get_preset(name).ok_or_else(|| {
+                let names: Vec<&str> = conf.get_presets().into_iter().map(|(n, _)| n).collect();
+                ParseError::new(format!(
+                    "unknown preset ({}), available presets: [{}]",
+                    name,
+                    names.join(", ")
+                ))
+            })?),
+            None => None,
         };
 
-        let mut break_time = match conf.get_break_time() {
-            Some(break_time) => break_time,
-            None => DEFAULT_BREAK_TIME,
-        };
+        // presets and defaults are configured in minutes
+        let mut work_time = match (preset, conf.get_work_time()) {
+            (Some(preset), _) => preset.work,
+            (None, Some(work_time)) => work_time,
+            (None, None) => DEFAULT_WORK_TIME,
+        } as u32
+            * 60;
+
+        let mut break_time = match (preset, conf.get_break_time()) {
+            (Some(preset), _) => preset.r#break,
+            (None, Some(break_time)) => break_time,
+            (None, None) => DEFAULT_BREAK_TIME,
+        } as u32
+            * 60;
 
         let mut description = Some("A pomodoro".to_string());
 
-        if let Ok(val) = parse_arg::<u16>(matches, "work") {
+        if let Some(val) = parse_duration_arg(matches, "work")? {
             work_time = val;
         };
 
-        if let Ok(val) = parse_arg::<u16>(matches, "break") {
+        if let Some(val) = parse_duration_arg(matches, "break")? {
             break_time = val;
         };
 
@@ -37,27 +69,275 @@ pub fn parse_work_and_break_time(
             description = Some(val.to_string());
         };
 
-        Ok((Some(work_time), Some(break_time), description))
+        Ok((Some(work_time), Some(break_time), description, preset_name))
     } else {
-        let mut work_time = None;
-        let mut break_time = None;
+        let work_time = parse_duration_arg(matches, "work")?;
+        let break_time = parse_duration_arg(matches, "break")?;
         let mut description = None;
 
-        if let Ok(val) = parse_arg::<u16>(matches, "work") {
-            work_time = Some(val);
-        }
-
-        if let Ok(val) = parse_arg::<u16>(matches, "break") {
-            break_time = Some(val);
-        }
         if let Some(val) = matches.get_one::<String>("description") {
             description = Some(val.to_string());
         };
 
-        Ok((work_time, break_time, description))
+        Ok((work_time, break_time, description, preset_name))
+    }
+}
+
+// parse_duration reads `45` (minutes), `45m`, `90s`, `1h30m` or `1h30m15s` as seconds
+pub fn parse_duration(duration: &str) -> Result<u32, ParseError> {
+    let error = || {
+        ParseError::new(format!(
+            "failed to parse duration ({}), use e.g. 45, 45m, 90s or 1h30m",
+            duration
+        ))
+    };
+
+    if let Ok(minutes) = duration.parse::<u32>() {
+        return minutes.checked_mul(60).ok_or_else(error);
+    }
+
+    let mut seconds: u32 = 0;
+    let mut number = String::new();
+    let mut last_unit = None;
+    for c in duration.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(error()),
+        };
+        // units are written from the largest, each of them once
+        if number.is_empty() || last_unit.is_some_and(|last| unit >= last) {
+            return Err(error());
+        }
+
+        seconds = number
+            .parse::<u32>()
+            .ok()
+            .and_then(|value| value.checked_mul(unit))
+            .and_then(|value| value.checked_add(seconds))
+            .ok_or_else(error)?;
+        number.clear();
+        last_unit = Some(unit);
+    }
+
+    if !number.is_empty() || last_unit.is_none() {
+        return Err(error());
+    }
+
+    Ok(seconds)
+}
+
+// durations are limited to what was accepted when they were stored in minutes
+const MAX_DURATION_SECONDS: u32 = u16::MAX as u32 * 60;
+
+fn parse_duration_arg(matches: &ArgMatches, arg_name: &str) -> Result<Option<u32>, ParseError> {
+    let duration = match matches.get_one::<String>(arg_name) {
+        Some(duration) => duration,
+        None => return Ok(None),
+    };
+
+    match parse_duration(duration)? {
+        seconds if seconds > MAX_DURATION_SECONDS => Err(ParseError::new(format!(
+            "duration ({}) is too long",
+            duration
+        ))),
+        seconds => Ok(Some(seconds)),
+    }
+}
+
+// parse_start_at returns when a created notification starts, given by `--at` or `--in`
+pub fn parse_start_at<Tz: TimeZone>(
+    matches: &ArgMatches,
+    now: &DateTime<Tz>,
+) -> Result<Option<DateTime<Utc>>, ParseError> {
+    resolve_start_at(
+        matches.get_one::<String>("at").map(|s| s.as_str()),
+        matches.get_one::<String>("in").map(|s| s.as_str()),
+        now,
+    )
+}
+
+pub fn resolve_start_at<Tz: TimeZone>(
+    at: Option<&str>,
+    r#in: Option<&str>,
+    now: &DateTime<Tz>,
+) -> Result<Option<DateTime<Utc>>, ParseError> {
+    match (at, r#in) {
+        (Some(at), _) => parse_time_of_day(at, now).map(Some),
+        (None, Some(r#in)) => {
+            let seconds = parse_duration(r#in)?;
+            Ok(Some(
+                now.with_timezone(&Utc) + Duration::seconds(seconds as i64),
+            ))
+        }
+        (None, None) => Ok(None),
     }
 }
 
+// parse_time_of_day reads `14:00`, `14:00:30` or a rfc3339 time.
+// A time of day which already passed today is tomorrow
+pub fn parse_time_of_day<Tz: TimeZone>(
+    at: &str,
+    now: &DateTime<Tz>,
+) -> Result<DateTime<Utc>, ParseError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(at) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let time = NaiveTime::parse_from_str(at, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(at, "%H:%M:%S"))
+        .map_err(|_| {
+            ParseError::new(format!(
+                "failed to parse time ({}), use e.g. 14:00 or 14:00:30",
+                at
+            ))
+        })?;
+
+    let today = now.date_naive().and_time(time);
+    [today, today + Duration::days(1)]
+        .into_iter()
+        // a time skipped by a daylight saving change doesn't exist
+        .filter_map(|start| now.timezone().from_local_datetime(&start).earliest())
+        .find(|start| start > now)
+        .map(|start| start.with_timezone(&Utc))
+        .ok_or_else(|| ParseError::new(format!("time ({}) does not exist", at)))
+}
+
+// parse_preset_name rejects names which can't be in the configuration, see `check_preset_name`
+pub fn parse_preset_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("invalid preset name ({})", name));
+    }
+
+    Ok(name.to_string())
+}
+
+// labels can't hold whitespace, the table and csv output join the tags with spaces
+pub fn parse_label(label: &str) -> Result<String, String> {
+    if label.is_empty() || label.contains(char::is_whitespace) {
+        return Err(format!("invalid tag or project ({})", label));
+    }
+
+    Ok(label.to_string())
+}
+
+// parse_labels returns the tags and the project of a created notification
+pub fn parse_labels(matches: &ArgMatches) -> (Vec<String>, Option<String>) {
+    let tags = matches
+        .get_many::<String>("tag")
+        .map(|tags| tags.cloned().collect())
+        .unwrap_or_default();
+    let project = matches.get_one::<String>("project").cloned();
+
+    (tags, project)
+}
+
+// parse_label_filter returns the `--tag` and `--project` filters of list and history
+pub fn parse_label_filter(matches: &ArgMatches) -> (Option<String>, Option<String>) {
+    (
+        matches.get_one::<String>("tag").cloned(),
+        matches.get_one::<String>("project").cloned(),
+    )
+}
+
+// notifications are picked by their uuid or its first characters
+pub fn parse_id_prefix(id: &str) -> Result<String, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(format!("invalid notification id ({})", id));
+    }
+
+    Ok(id.to_lowercase())
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date ({}), expected like 2024-03-01", date))
+}
+
+// parse_stats_range returns the range of `stats`, today without any range argument
+pub fn parse_stats_range(matches: &ArgMatches, today: NaiveDate) -> Result<StatsRange, ParseError> {
+    let period = ["day", "week", "month"]
+        .into_iter()
+        .find(|period| matches.get_flag(period));
+
+    resolve_stats_range(
+        period,
+        matches.get_one::<NaiveDate>("from").copied(),
+        matches.get_one::<NaiveDate>("to").copied(),
+        today,
+    )
+}
+
+// resolve_stats_range takes a period (`day`, `week` or `month`) or the first and last days
+pub fn resolve_stats_range(
+    period: Option<&str>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Result<StatsRange, ParseError> {
+    match (period, from, to) {
+        (Some("day"), None, None) | (None, None, None) => Ok(StatsRange::day(today)),
+        (Some("week"), None, None) => Ok(StatsRange::week(today)),
+        (Some("month"), None, None) => Ok(StatsRange::month(today)),
+        (None, Some(from), to) => {
+            let to = to.unwrap_or(today);
+            if from > to {
+                return Err(ParseError::new(format!(
+                    "the first day ({}) is after the last day ({})",
+                    from, to
+                )));
+            }
+
+            Ok(StatsRange { from, to })
+        }
+        (None, None, Some(_)) => Err(ParseError::new(String::from(
+            "the last day is given without the first day",
+        ))),
+        (Some(period), _, _) => Err(ParseError::new(format!(
+            "invalid period ({}), expected day, week or month without dates",
+            period
+        ))),
+    }
+}
+
+// notes are shown in tables and notifications, a longer text is most likely pasted by mistake
+const NOTE_MAX_LENGTH: usize = 1000;
+
+// parse_note joins the words of a free text argument given without quotes and checks the text
+pub fn parse_note(matches: &ArgMatches) -> Result<Option<String>, ParseError> {
+    matches
+        .get_many::<String>("note")
+        .map(|words| check_note(&words.cloned().collect::<Vec<String>>().join(" ")))
+        .transpose()
+        .map_err(ParseError::new)
+}
+
+// check_note trims a note and rejects an empty or very long one, or one with control
+// characters which would break the table output
+pub fn check_note(note: &str) -> Result<String, String> {
+    let note = note.trim();
+    if note.is_empty() {
+        return Err(String::from("note is empty"));
+    }
+    if note.chars().count() > NOTE_MAX_LENGTH {
+        return Err(format!(
+            "note is longer than {} characters",
+            NOTE_MAX_LENGTH
+        ));
+    }
+    if note.chars().any(char::is_control) {
+        return Err(String::from("note contains control characters"));
+    }
+
+    Ok(note.to_string())
+}
+
 pub fn parse_shell(matches: &ArgMatches) -> Option<Shell> {
     let shell = matches.get_one::<String>("shell");
     if let Some(shell) = shell {
@@ -74,6 +354,49 @@ pub fn parse_shell(matches: &ArgMatches) -> Option<Shell> {
     }
 }
 
+pub fn parse_status_format(matches: &ArgMatches) -> Result<StatusFormat, ParseError> {
+    let format = matches
+        .get_one::<String>("format")
+        .map(|s| s.as_str())
+        .unwrap_or("plain");
+
+    match format {
+        "json" => Ok(StatusFormat::Json),
+        "plain" => Ok(StatusFormat::Plain),
+        "template" => {
+            let template = matches
+                .get_one::<String>("template")
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| DEFAULT_STATUS_TEMPLATE.to_string());
+            Ok(StatusFormat::Template(template))
+        }
+        _ => Err(ParseError::new(format!(
+            "failed to parse str ({}) to status format",
+            format
+        ))),
+    }
+}
+
+pub fn parse_output_format(matches: &ArgMatches) -> Result<OutputFormat, ParseError> {
+    match matches.get_one::<String>("output").map(|s| s.as_str()) {
+        None | Some("table") => Ok(OutputFormat::Table),
+        Some("json") => Ok(OutputFormat::Json),
+        Some("csv") => Ok(OutputFormat::Csv),
+        Some(s) => Err(ParseError::new(format!(
+            "failed to parse str ({}) to output format",
+            s
+        ))),
+    }
+}
+
+// get_leaf_matches returns the matches of the innermost subcommand, where global args end up
+pub fn get_leaf_matches(matches: &ArgMatches) -> &ArgMatches {
+    match matches.subcommand() {
+        Some((_, sub_matches)) => get_leaf_matches(sub_matches),
+        None => matches,
+    }
+}
+
 pub fn parse_arg<C>(arg_matches: &ArgMatches, arg_name: &str) -> Result<C, ParseError>
 where
     C: FromStr,
@@ -91,7 +414,19 @@ where
     Ok(parsed)
 }
 
+pub fn disable_prompt() {
+    PROMPT_ENABLED.store(false, Ordering::Relaxed);
+}
+
+pub fn is_prompt_enabled() -> bool {
+    PROMPT_ENABLED.load(Ordering::Relaxed)
+}
+
 pub fn print_start_up() {
+    if !PROMPT_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
     let stdout = &mut io::stdout();
     write!(stdout, "> ").unwrap();
     stdout.flush().expect("could not flush stdout");
@@ -101,6 +436,10 @@ pub fn write_output<W>(stdout: &mut W)
 where
     W: Write,
 {
+    if !PROMPT_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
     write!(stdout, "> ").unwrap();
     stdout.flush().expect("couldn't flush stdout");
 }
@@ -108,8 +447,25 @@ where
 #[cfg(test)]
 mod tests {
     use clap::{Arg, Command};
+    use std::sync::Arc;
+
+    use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+
+    use super::{
+        check_note, parse_arg, parse_duration, parse_preset_name, parse_start_at,
+        parse_stats_range, parse_time_of_day, parse_work_and_break_time,
+    };
+    use crate::command::add_args_for_create_subcommand;
+    use crate::configuration::Configuration;
 
-    use super::parse_arg;
+    #[test]
+    fn test_check_note() {
+        assert_eq!(check_note("  phone call \n").unwrap(), "phone call");
+        assert_eq!(check_note("-e it is").unwrap(), "-e it is");
+        assert!(check_note("   ").is_err());
+        assert!(check_note("line\nbreak").is_err());
+        assert!(check_note(&"a".repeat(1001)).is_err());
+    }
 
     #[test]
     fn test_parse_arg() {
@@ -134,4 +490,136 @@ mod tests {
             .to_string()
             .contains("failed to parse arg"));
     }
+
+    #[test]
+    fn test_parse_preset_name() {
+        assert_eq!(parse_preset_name("deep").as_deref(), Ok("deep"));
+        assert!(parse_preset_name("deep -w 90").is_err());
+        assert!(parse_preset_name("").is_err());
+    }
+
+    #[test]
+    fn test_parse_work_and_break_time_with_preset() {
+        let configuration: Configuration = toml::from_str(
+            "work_time_default_value = 30\n[presets]\ndeep = { work = 50, break = 10 }\n",
+        )
+        .unwrap();
+        let configuration = Arc::new(configuration);
+        let parse = |args: &str| {
+            let matches = add_args_for_create_subcommand(Command::new("create"))
+                .get_matches_from(args.split_whitespace());
+            parse_work_and_break_time(&matches, Some(&configuration))
+        };
+
+        let (work, r#break, _, preset) = parse("create -p deep").unwrap();
+        assert_eq!((work, r#break), (Some(50 * 60), Some(10 * 60)));
+        assert_eq!(preset.as_deref(), Some("deep"));
+
+        let (work, r#break, _, _) = parse("create -p deep -b 0").unwrap();
+        assert_eq!((work, r#break), (Some(50 * 60), Some(0)));
+
+        let (work, _, _, preset) = parse("create").unwrap();
+        assert_eq!(work, Some(30 * 60));
+        assert!(preset.is_none());
+
+        let e = parse("create -p standard").unwrap_err();
+        assert!(e.to_string().contains("available presets: [deep]"), "{}", e);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45").unwrap(), 45 * 60);
+        assert_eq!(parse_duration("45m").unwrap(), 45 * 60);
+        assert_eq!(parse_duration("90s").unwrap(), 90);
+        assert_eq!(parse_duration("1h30m").unwrap(), 90 * 60);
+        assert_eq!(parse_duration("1h0m15s").unwrap(), 3615);
+
+        for duration in ["", "m", "1x", "30m1h", "1h1h", "10m5", "-5m", "99999999999"] {
+            assert!(parse_duration(duration).is_err(), "{}", duration);
+        }
+    }
+
+    #[test]
+    fn test_parse_work_and_break_durations() {
+        let parse = |args: &str| {
+            let matches = add_args_for_create_subcommand(Command::new("create"))
+                .get_matches_from(args.split_whitespace());
+            parse_work_and_break_time(&matches, None)
+        };
+
+        let (work, r#break, _, _) = parse("create -w 1h30m -b 300s").unwrap();
+        assert_eq!((work, r#break), (Some(90 * 60), Some(300)));
+        let (work, r#break, _, _) = parse("create -w 90s -b 15").unwrap();
+        assert_eq!((work, r#break), (Some(90), Some(15 * 60)));
+        assert!(parse("create -w 2000h").is_err());
+        assert!(parse("create -w abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_start_at() {
+        let timezone = FixedOffset::east_opt(2 * 3600).unwrap();
+        let now = timezone.with_ymd_and_hms(2024, 5, 1, 13, 0, 0).unwrap();
+
+        let start = parse_time_of_day("14:00", &now).unwrap();
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+
+        // already passed, starts tomorrow
+        let start = parse_time_of_day("12:30:15", &now).unwrap();
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 5, 2, 10, 30, 15).unwrap());
+
+        let start = parse_time_of_day("2024-05-03T08:00:00+00:00", &now).unwrap();
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 5, 3, 8, 0, 0).unwrap());
+        assert!(parse_time_of_day("25:00", &now).is_err());
+
+        let command = || {
+            add_args_for_create_subcommand(Command::new("create"))
+                .arg(clap::Arg::new("at").long("at"))
+                .arg(clap::Arg::new("in").long("in"))
+        };
+        let matches = command().get_matches_from("create --in 20m".split_whitespace());
+        let start = parse_start_at(&matches, &now).unwrap();
+        assert_eq!(
+            start,
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 11, 20, 0).unwrap())
+        );
+
+        let matches = command().get_matches_from("create".split_whitespace());
+        assert_eq!(parse_start_at(&matches, &now).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_stats_range() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 6).unwrap();
+        let range = |input: &str| {
+            let matches = crate::command::get_main_command()
+                .try_get_matches_from(input.split_whitespace())
+                .unwrap();
+            let (_, sub_matches) = matches.subcommand().unwrap();
+            parse_stats_range(sub_matches, today)
+                .map(|range| (range.from.to_string(), range.to.to_string()))
+        };
+
+        let expected = |from: &str, to: &str| (from.to_string(), to.to_string());
+        assert_eq!(
+            range("stats").unwrap(),
+            expected("2024-03-06", "2024-03-06")
+        );
+        assert_eq!(
+            range("stats --week").unwrap(),
+            expected("2024-03-04", "2024-03-10")
+        );
+        assert_eq!(
+            range("stats --month").unwrap(),
+            expected("2024-03-01", "2024-03-31")
+        );
+        assert_eq!(
+            range("stats --from 2024-02-20").unwrap(),
+            expected("2024-02-20", "2024-03-06")
+        );
+        assert_eq!(
+            range("stats --from 2024-02-20 --to 2024-02-25").unwrap(),
+            expected("2024-02-20", "2024-02-25")
+        );
+        assert!(range("stats --from 2024-03-10").is_err());
+    }
 }
@@ -0,0 +1,249 @@
+use chrono::NaiveTime;
+use reqwest::Url;
+use serde_json::Value;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::error::ConfigurationError;
+
+/// FileLocation is a 1-based position in the configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for FileLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+impl FileLocation {
+    pub fn from_offset(content: &str, offset: usize) -> Self {
+        let before = &content[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+        FileLocation {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+// find_key_location looks for where `section.key` is written in json, toml or yaml,
+// each part is searched after the previous one
+pub fn find_key_location(content: &str, key: &str) -> Option<FileLocation> {
+    let mut offset = 0;
+    for part in key.split('.') {
+        offset = find_key(content, part, offset)?;
+    }
+
+    Some(FileLocation::from_offset(content, offset))
+}
+
+fn find_key(content: &str, key: &str, from: usize) -> Option<usize> {
+    let mut search = from;
+    while let Some(i) = content[search..].find(key) {
+        let start = search + i;
+        let end = start + key.len();
+
+        let before = content[..start].chars().next_back();
+        let after = content[end..].trim_start_matches(['"', '\'']).trim_start();
+        let is_key_start = matches!(
+            before,
+            None | Some(' ' | '\t' | '\n' | '"' | '\'' | '{' | ',' | '[' | '.')
+        );
+        let is_key_end = after.starts_with([':', '=', ']', '.']);
+        if is_key_start && is_key_end {
+            return Some(start);
+        }
+
+        search = end;
+    }
+
+    None
+}
+
+// get_unknown_keys compares the keys of the file with the keys the configuration kept,
+// anything else was ignored by serde
+pub fn get_unknown_keys(input: &Value, known: &Value) -> Vec<String> {
+    let mut unknown = Vec::new();
+    collect_unknown_keys("", input, known, &mut unknown);
+
+    unknown
+}
+
+fn collect_unknown_keys(prefix: &str, input: &Value, known: &Value, unknown: &mut Vec<String>) {
+    let join = |key: &str| match prefix {
+        "" => key.to_string(),
+        _ => format!("{}.{}", prefix, key),
+    };
+
+    match (input, known) {
+        (Value::Object(input), Value::Object(known)) => {
+            for (key, value) in input.iter() {
+                match known.get(key) {
+                    Some(known_value) => {
+                        collect_unknown_keys(&join(key), value, known_value, unknown)
+                    }
+                    None => unknown.push(join(key)),
+                }
+            }
+        }
+        (Value::Array(input), Value::Array(known)) => {
+            for (i, (value, known_value)) in input.iter().zip(known.iter()).enumerate() {
+                collect_unknown_keys(&join(&i.to_string()), value, known_value, unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn check_range(
+    key: &str,
+    value: Option<u16>,
+    range: RangeInclusive<u16>,
+    content: &str,
+) -> Option<ConfigurationError> {
+    let value = value?;
+    if range.contains(&value) {
+        return None;
+    }
+
+    Some(ConfigurationError::OutOfRange(
+        key.to_string(),
+        format!(
+            "{} is not between {} and {}",
+            value,
+            range.start(),
+            range.end()
+        ),
+        find_key_location(content, key),
+    ))
+}
+
+// check_url accepts absolute http(s) urls
+pub fn check_url(key: &str, value: Option<&str>, content: &str) -> Option<ConfigurationError> {
+    let reason = match Url::parse(value?) {
+        Ok(url) if !matches!(url.scheme(), "http" | "https") => {
+            format!("scheme ({}) is not http or https", url.scheme())
+        }
+        Ok(url) if url.host_str().is_none() => String::from("host is missing"),
+        Ok(_) => return None,
+        Err(e) => e.to_string(),
+    };
+
+    Some(ConfigurationError::InvalidUrl(
+        key.to_string(),
+        reason,
+        find_key_location(content, key),
+    ))
+}
+
+// times of day are written like `21:00`
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+pub fn check_time(key: &str, value: Option<&str>, content: &str) -> Option<ConfigurationError> {
+    let value = value?;
+    if parse_time(value).is_some() {
+        return None;
+    }
+
+    Some(ConfigurationError::InvalidTime(
+        key.to_string(),
+        format!("{} is not a time like 21:00", value),
+        find_key_location(content, key),
+    ))
+}
+
+// preset names are passed as a single command line argument
+pub fn check_preset_name(name: &str, content: &str) -> Option<ConfigurationError> {
+    let is_valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if is_valid {
+        return None;
+    }
+
+    Some(ConfigurationError::InvalidPresetName(
+        name.to_string(),
+        find_key_location(content, &format!("presets.{}", name)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        check_preset_name, check_range, check_time, check_url, find_key_location, get_unknown_keys,
+        FileLocation,
+    };
+
+    #[test]
+    fn test_find_key_location() {
+        let json = "{\n  \"slack\": {\n    \"channel\": \"a\"\n  },\n  \"work_time_default\": 3\n}";
+        assert_eq!(
+            find_key_location(json, "slack.channel"),
+            Some(FileLocation { line: 3, column: 6 })
+        );
+        assert_eq!(
+            find_key_location(json, "work_time_default"),
+            Some(FileLocation { line: 5, column: 4 })
+        );
+        assert_eq!(find_key_location(json, "work_time"), None);
+
+        let toml = "work_time_default_value = 0\n\n[discord]\nwebhook_url = \"x\"\n";
+        assert_eq!(
+            find_key_location(toml, "discord.webhook_url"),
+            Some(FileLocation { line: 4, column: 1 })
+        );
+
+        let yaml = "http:\n  port: 0\n";
+        assert_eq!(
+            find_key_location(yaml, "http.port"),
+            Some(FileLocation { line: 2, column: 3 })
+        );
+    }
+
+    #[test]
+    fn test_get_unknown_keys() {
+        let input = json!({
+            "work_time_default": 30,
+            "slack": { "channel": "a", "chanel": "b" },
+            "http": null,
+        });
+        let known = json!({
+            "work_time_default_value": null,
+            "slack": { "channel": "a", "token": null },
+            "http": null,
+        });
+        assert_eq!(
+            get_unknown_keys(&input, &known),
+            vec!["slack.chanel", "work_time_default"]
+        );
+    }
+
+    #[test]
+    fn test_checks() {
+        assert!(check_range("a", None, 1..=10, "").is_none());
+        assert!(check_range("a", Some(10), 1..=10, "").is_none());
+        assert!(check_range("a", Some(0), 1..=10, "").is_some());
+
+        assert!(check_url("a", Some("https://discord.com/api/webhooks/1"), "").is_none());
+        assert!(check_url("a", Some("your-webhook-url"), "").is_some());
+        assert!(check_url("a", Some("ftp://example.com"), "").is_some());
+
+        assert!(check_time("a", Some("21:00"), "").is_none());
+        assert!(check_time("a", Some("9pm"), "").is_some());
+        assert!(check_time("a", Some("25:00"), "").is_some());
+
+        assert!(check_preset_name("meeting-prep", "").is_none());
+        assert!(check_preset_name("deep work", "").is_some());
+        assert!(check_preset_name("", "").is_some());
+    }
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::command::action::ActionType;
+use crate::{InputSource, UserInput};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// modification time and size are enough to notice an edit, including editors replacing the file
+type FileState = Option<(SystemTime, u64)>;
+
+fn get_file_state(path: &Path) -> FileState {
+    fs::metadata(path)
+        .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+        .ok()
+}
+
+/// Polls the configuration file and asks the server to reload it when it changes
+pub fn handle(path: PathBuf, tx: Sender<UserInput>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        debug!("watching configuration file {:?}", path);
+        let mut last_state = get_file_state(&path);
+
+        loop {
+            sleep(POLL_INTERVAL).await;
+
+            let state = get_file_state(&path);
+            // a missing file is mostly an editor in the middle of saving, wait for it to come back
+            if state.is_none() || state == last_state {
+                continue;
+            }
+            last_state = state;
+
+            info!("configuration file {:?} changed, reloading", path);
+            let reload = UserInput {
+                input: vec![String::from(ActionType::ReloadConfig)],
+                source: InputSource::ConfigurationWatcher,
+            };
+            if tx.send(reload).await.is_err() {
+                break;
+            }
+        }
+    })
+}
@@ -1,46 +1,141 @@
+use chrono::NaiveTime;
 use clap::ArgMatches;
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
-use std::ffi::OsStr;
-use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::config_validation::{
+    check_preset_name, check_range, check_time, check_url, find_key_location, get_unknown_keys,
+    parse_time, FileLocation,
+};
+use crate::credential::Credential;
 use crate::error::ConfigurationError;
+use crate::goal::DailyGoal;
 use crate::report::generate_configuration_report;
 
 pub const SLACK_API_URL: &str = "https://slack.com/api/chat.postMessage";
+pub const DEFAULT_HTTP_PORT: u16 = 7878;
+// default work and break times are between a minute and a day
+const MINUTES_RANGE: RangeInclusive<u16> = 1..=1440;
+
+const CONFIG_DIR_NAME: &str = "pomodoro";
+const CONFIG_FILE_STEM: &str = "config";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigurationFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigurationFormat {
+    // discovery order in the config directory
+    const ALL: [ConfigurationFormat; 3] = [
+        ConfigurationFormat::Toml,
+        ConfigurationFormat::Yaml,
+        ConfigurationFormat::Json,
+    ];
+
+    // files without a known extension are read as json, as they always were
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .as_deref()
+        {
+            Some("toml") => ConfigurationFormat::Toml,
+            Some("yaml") | Some("yml") => ConfigurationFormat::Yaml,
+            _ => ConfigurationFormat::Json,
+        }
+    }
+
+    fn get_extension(&self) -> &'static str {
+        match self {
+            ConfigurationFormat::Json => "json",
+            ConfigurationFormat::Toml => "toml",
+            ConfigurationFormat::Yaml => "yaml",
+        }
+    }
+}
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Configuration {
-    #[serde(rename(deserialize = "slack"))]
+    #[serde(rename = "slack")]
     slack_configuration: Option<SlackConfiguration>,
-    #[serde(rename(deserialize = "discord"))]
+    #[serde(rename = "discord")]
     discord_configuration: Option<DiscordConfiguration>,
+    #[serde(rename = "http")]
+    http_configuration: Option<HttpConfiguration>,
     work_time_default_value: Option<u16>,
     break_time_default_value: Option<u16>,
+    // send a summary of notifications which ended while the server was down
+    missed_notification_summary: Option<bool>,
+    // ask in the prompt for a note and a focus rating when a work time ends
+    review_prompt: Option<bool>,
+    presets: Option<BTreeMap<String, Preset>>,
+    #[serde(rename = "daily_goal")]
+    daily_goal_configuration: Option<DailyGoalConfiguration>,
+    // the file this configuration was read from, used by `reload-config`
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    #[serde(skip)]
+    warnings: Vec<Arc<ConfigurationError>>,
+}
+
+/// Preset is a named pair of durations used by `create --preset <name>`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub work: u16,
+    pub r#break: u16,
 }
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 struct SlackConfiguration {
-    token: Option<String>,
+    token: Option<Credential>,
     channel: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 struct DiscordConfiguration {
-    webhook_url: Option<String>,
+    webhook_url: Option<Credential>,
+}
+
+// the goal is either a number of pomodoros or of focus minutes
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct DailyGoalConfiguration {
+    pomodoros: Option<u16>,
+    minutes: Option<u16>,
+    // local time like `21:00` to be reminded of a goal not reached yet
+    reminder: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct HttpConfiguration {
+    port: Option<u16>,
+    token: Option<Credential>,
 }
 
 impl Configuration {
     pub fn get_slack_token(&self) -> &Option<String> {
-        match &self.slack_configuration {
-            Some(config) => &config.token,
+        match self.get_slack_token_credential() {
+            Some(credential) => credential.get_value(),
             None => &None,
         }
     }
 
+    pub fn get_slack_token_credential(&self) -> Option<&Credential> {
+        self.slack_configuration
+            .as_ref()
+            .and_then(|config| config.token.as_ref())
+    }
+
     pub fn get_slack_channel(&self) -> &Option<String> {
         match &self.slack_configuration {
             Some(config) => &config.channel,
@@ -49,12 +144,88 @@ impl Configuration {
     }
 
     pub fn get_discord_webhook_url(&self) -> &Option<String> {
-        match &self.discord_configuration {
-            Some(config) => &config.webhook_url,
+        match self.get_discord_webhook_url_credential() {
+            Some(credential) => credential.get_value(),
             None => &None,
         }
     }
 
+    pub fn get_discord_webhook_url_credential(&self) -> Option<&Credential> {
+        self.discord_configuration
+            .as_ref()
+            .and_then(|config| config.webhook_url.as_ref())
+    }
+
+    pub fn get_http_port(&self) -> u16 {
+        self.http_configuration
+            .as_ref()
+            .and_then(|config| config.port)
+            .unwrap_or(DEFAULT_HTTP_PORT)
+    }
+
+    // the http api is served only when a token is configured
+    pub fn get_http_token(&self) -> &Option<String> {
+        match self.get_http_token_credential() {
+            Some(credential) => credential.get_value(),
+            None => &None,
+        }
+    }
+
+    pub fn get_http_token_credential(&self) -> Option<&Credential> {
+        self.http_configuration
+            .as_ref()
+            .and_then(|config| config.token.as_ref())
+    }
+
+    fn get_credentials_mut(&mut self) -> Vec<&mut Credential> {
+        let slack = self
+            .slack_configuration
+            .as_mut()
+            .and_then(|config| config.token.as_mut());
+        let discord = self
+            .discord_configuration
+            .as_mut()
+            .and_then(|config| config.webhook_url.as_mut());
+        let http = self
+            .http_configuration
+            .as_mut()
+            .and_then(|config| config.token.as_mut());
+
+        [slack, discord, http].into_iter().flatten().collect()
+    }
+
+    // resolve_credentials reads the secrets given by env, file or command
+    pub fn resolve_credentials(&mut self) {
+        self.get_credentials_mut()
+            .into_iter()
+            .for_each(|credential| credential.resolve());
+    }
+
+    // the configured secrets, keyed like in the file
+    fn get_credentials(&self) -> Vec<(&'static str, &Credential)> {
+        [
+            ("slack.token", self.get_slack_token_credential()),
+            (
+                "discord.webhook_url",
+                self.get_discord_webhook_url_credential(),
+            ),
+            ("http.token", self.get_http_token_credential()),
+        ]
+        .into_iter()
+        .filter_map(|(key, credential)| credential.map(|credential| (key, credential)))
+        .collect()
+    }
+
+    // get_credential_errors lists the secrets which could not be read
+    pub fn get_credential_errors(&self) -> Vec<String> {
+        self.get_credentials()
+            .into_iter()
+            .filter_map(|(key, credential)| {
+                credential.get_error().map(|e| format!("{}: {}", key, e))
+            })
+            .collect()
+    }
+
     pub fn get_work_time(&self) -> Option<u16> {
         self.work_time_default_value
     }
@@ -62,20 +233,221 @@ impl Configuration {
     pub fn get_break_time(&self) -> Option<u16> {
         self.break_time_default_value
     }
+
+    pub fn get_preset(&self, name: &str) -> Option<Preset> {
+        self.presets
+            .as_ref()
+            .and_then(|presets| presets.get(name).copied())
+    }
+
+    // the presets sorted by name
+    pub fn get_presets(&self) -> Vec<(&str, Preset)> {
+        match &self.presets {
+            Some(presets) => presets
+                .iter()
+                .map(|(name, preset)| (name.as_str(), *preset))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn get_missed_notification_summary(&self) -> bool {
+        self.missed_notification_summary.unwrap_or(false)
+    }
+
+    pub fn get_review_prompt(&self) -> bool {
+        self.review_prompt.unwrap_or(false)
+    }
+
+    pub fn get_daily_goal(&self) -> Option<DailyGoal> {
+        let config = self.daily_goal_configuration.as_ref()?;
+        match (config.pomodoros, config.minutes) {
+            (Some(pomodoros), None) => Some(DailyGoal::Pomodoros(pomodoros)),
+            (None, Some(minutes)) => Some(DailyGoal::Minutes(minutes)),
+            _ => None,
+        }
+    }
+
+    // the reminder is only sent with a daily goal, an invalid time is refused on load
+    pub fn get_daily_goal_reminder(&self) -> Option<NaiveTime> {
+        self.get_daily_goal()?;
+        self.daily_goal_configuration
+            .as_ref()
+            .and_then(|config| config.reminder.as_deref())
+            .and_then(parse_time)
+    }
+
+    pub fn get_path(&self) -> &Option<PathBuf> {
+        &self.path
+    }
+
+    // get_warnings lists what was ignored while reading the file
+    pub fn get_warnings(&self) -> Vec<&ConfigurationError> {
+        self.warnings
+            .iter()
+            .map(|warning| warning.as_ref())
+            .collect()
+    }
+
+    // validate checks the values serde accepts but the server can't use,
+    // content is the file the configuration was read from
+    fn validate(&self, content: &str) -> Vec<ConfigurationError> {
+        [
+            check_range(
+                "work_time_default_value",
+                self.work_time_default_value,
+                MINUTES_RANGE,
+                content,
+            ),
+            check_range(
+                "break_time_default_value",
+                self.break_time_default_value,
+                MINUTES_RANGE,
+                content,
+            ),
+            check_range(
+                "http.port",
+                self.http_configuration
+                    .as_ref()
+                    .and_then(|config| config.port),
+                1..=u16::MAX,
+                content,
+            ),
+            check_url(
+                "discord.webhook_url",
+                self.get_discord_webhook_url().as_deref(),
+                content,
+            ),
+            check_daily_goal(self.daily_goal_configuration.as_ref(), content),
+            check_time(
+                "daily_goal.reminder",
+                self.daily_goal_configuration
+                    .as_ref()
+                    .and_then(|config| config.reminder.as_deref()),
+                content,
+            ),
+        ]
+        .into_iter()
+        .chain(self.get_presets().into_iter().flat_map(|(name, preset)| {
+            [
+                check_preset_name(name, content),
+                check_range(
+                    &format!("presets.{}.work", name),
+                    Some(preset.work),
+                    MINUTES_RANGE,
+                    content,
+                ),
+                // a preset without break is fine, e.g. right before a meeting
+                check_range(
+                    &format!("presets.{}.break", name),
+                    Some(preset.r#break),
+                    0..=*MINUTES_RANGE.end(),
+                    content,
+                ),
+            ]
+        }))
+        .flatten()
+        .collect()
+    }
+
+    // get_redacted hides the secrets, used to print the configuration
+    pub fn get_redacted(&self) -> Configuration {
+        let mut configuration = self.clone();
+        configuration
+            .get_credentials_mut()
+            .into_iter()
+            .for_each(|credential| *credential = credential.get_redacted());
+
+        configuration
+    }
+
+    pub fn serialize(&self, format: ConfigurationFormat) -> Result<String, ConfigurationError> {
+        match format {
+            ConfigurationFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(ConfigurationError::JsonError)
+            }
+            ConfigurationFormat::Toml => {
+                toml::to_string_pretty(self).map_err(ConfigurationError::TomlSerializeError)
+            }
+            ConfigurationFormat::Yaml => {
+                serde_yaml::to_string(self).map_err(ConfigurationError::YamlError)
+            }
+        }
+    }
+}
+
+fn check_daily_goal(
+    config: Option<&DailyGoalConfiguration>,
+    content: &str,
+) -> Option<ConfigurationError> {
+    match (config?.pomodoros, config?.minutes) {
+        (Some(pomodoros), None) => check_range(
+            "daily_goal.pomodoros",
+            Some(pomodoros),
+            MINUTES_RANGE,
+            content,
+        ),
+        (None, Some(minutes)) => {
+            check_range("daily_goal.minutes", Some(minutes), MINUTES_RANGE, content)
+        }
+        _ => Some(ConfigurationError::OutOfRange(
+            String::from("daily_goal"),
+            String::from("give either pomodoros or minutes"),
+            find_key_location(content, "daily_goal"),
+        )),
+    }
 }
 
 pub fn get_configuration(matches: &ArgMatches) -> Result<Arc<Configuration>, ConfigurationError> {
-    let configuration_file_path = matches.get_one::<String>("config").map(|s| s.as_str());
+    let configuration_file_path = get_configuration_path(matches)?;
 
-    let (configuration, config_error) = load_configuration(configuration_file_path)?;
+    let (configuration, config_error) = load_configuration(configuration_file_path.as_deref())?;
     let report = generate_configuration_report(&configuration, config_error);
     info!("\nconfig flag result!\n{}", report);
 
     Ok(Arc::new(configuration))
 }
 
+// get_configuration_path returns the `--config` file, resolved against the current directory,
+// or the first config file found in the config directory
+pub fn get_configuration_path(matches: &ArgMatches) -> Result<Option<PathBuf>, ConfigurationError> {
+    match matches.get_one::<String>("config") {
+        Some(f) => {
+            let path = env::current_dir()
+                .map_err(ConfigurationError::LoadFail)?
+                .join(f);
+            Ok(Some(path))
+        }
+        None => Ok(get_config_dir().and_then(|dir| find_configuration_file(&dir))),
+    }
+}
+
+// `$XDG_CONFIG_HOME/pomodoro`, or `~/.config/pomodoro` when it is not set
+pub fn get_config_dir() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_home.join(CONFIG_DIR_NAME))
+}
+
+// the files looked up in the config directory, in order
+pub fn get_candidate_files(dir: &Path) -> Vec<PathBuf> {
+    ConfigurationFormat::ALL
+        .iter()
+        .map(|format| dir.join(format!("{}.{}", CONFIG_FILE_STEM, format.get_extension())))
+        .collect()
+}
+
+fn find_configuration_file(dir: &Path) -> Option<PathBuf> {
+    get_candidate_files(dir)
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
 pub fn load_configuration(
-    configuration_file: Option<&str>,
+    configuration_file: Option<&Path>,
 ) -> Result<(Configuration, Option<ConfigurationError>), ConfigurationError> {
     let (configuration, error) = match configuration_file {
         Some(f) => {
@@ -83,9 +455,22 @@ pub fn load_configuration(
                 .map_err(ConfigurationError::LoadFail)?
                 .join(f);
 
-            match get_configuration_from_file(path) {
-                Ok(config) => (config, None),
-                Err(e) => (Configuration::default(), Some(e)),
+            match read_configuration_file(&path) {
+                Ok(config) => (
+                    Configuration {
+                        path: Some(path),
+                        ..config
+                    },
+                    None,
+                ),
+                // the path is kept so the file is watched and reloaded once it is fixed
+                Err(e) => (
+                    Configuration {
+                        path: Some(path),
+                        ..Configuration::default()
+                    },
+                    Some(e),
+                ),
             }
         }
         None => (Configuration::default(), None),
@@ -96,32 +481,196 @@ pub fn load_configuration(
     Ok((configuration, error))
 }
 
-fn get_configuration_from_file<P: AsRef<Path> + AsRef<OsStr>>(
-    path: P,
+// run_blocking reads the configuration off the async runtime, a credential command may take
+// until its timeout
+pub async fn run_blocking<T, F>(read: F) -> Result<T, ConfigurationError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ConfigurationError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(read)
+        .await
+        .map_err(|e| ConfigurationError::LoadFail(io::Error::other(e)))?
+}
+
+// reload_configuration re-reads the file the given configuration was loaded from
+pub fn reload_configuration(
+    configuration: &Configuration,
 ) -> Result<Configuration, ConfigurationError> {
-    if !Path::new(&path).exists() {
+    let path = configuration
+        .path
+        .as_ref()
+        .ok_or(ConfigurationError::NotLoadedFromFile)?;
+
+    let reloaded = read_configuration_file(path)?;
+    debug!("reloaded configuration: {:?}", reloaded);
+
+    // keep the current secrets rather than dropping a channel
+    let credential_errors = reloaded.get_credential_errors();
+    if !credential_errors.is_empty() {
+        return Err(ConfigurationError::CredentialUnavailable(
+            credential_errors.join(", "),
+        ));
+    }
+
+    Ok(Configuration {
+        path: Some(path.clone()),
+        ..reloaded
+    })
+}
+
+// get_configuration_diff describes what changed as `key: old -> new` lines, secrets are only
+// reported as changed
+pub fn get_configuration_diff(old: &Configuration, new: &Configuration) -> Vec<String> {
+    let old_values = flatten_configuration(old);
+    let new_values = flatten_configuration(new);
+    let none = String::from("(none)");
+
+    let mut keys = old_values
+        .keys()
+        .chain(new_values.keys())
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+
+    let mut diff = keys
+        .into_iter()
+        .filter_map(|key| {
+            let (old_value, new_value) = (old_values.get(key), new_values.get(key));
+            (old_value != new_value).then(|| {
+                format!(
+                    "{}: {} -> {}",
+                    key,
+                    old_value.unwrap_or(&none),
+                    new_value.unwrap_or(&none)
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // a secret written in the file is redacted above, compare the values themselves
+    for (key, credential) in new.get_credentials() {
+        let old_credential = old.get_credentials().into_iter().find(|(k, _)| *k == key);
+        let is_changed = match old_credential {
+            Some((_, old_credential)) => old_credential.get_value() != credential.get_value(),
+            None => false,
+        };
+        if is_changed
+            && !diff
+                .iter()
+                .any(|line| line.starts_with(&format!("{}:", key)))
+        {
+            diff.push(format!("{}: changed", key));
+        }
+    }
+
+    diff
+}
+
+// flatten_configuration maps `section.key` to the value, unset values are left out
+fn flatten_configuration(configuration: &Configuration) -> BTreeMap<String, String> {
+    fn flatten(prefix: &str, value: &serde_json::Value, values: &mut BTreeMap<String, String>) {
+        match value {
+            serde_json::Value::Object(map) => map.iter().for_each(|(key, value)| {
+                let key = match prefix {
+                    "" => key.clone(),
+                    _ => format!("{}.{}", prefix, key),
+                };
+                flatten(&key, value, values);
+            }),
+            serde_json::Value::Null => {}
+            _ => {
+                values.insert(prefix.to_string(), value.to_string());
+            }
+        }
+    }
+
+    let mut values = BTreeMap::new();
+    if let Ok(value) = serde_json::to_value(configuration.get_redacted()) {
+        flatten("", &value, &mut values);
+    }
+
+    values
+}
+
+fn parse_content<T: DeserializeOwned>(
+    content: &str,
+    format: ConfigurationFormat,
+) -> Result<T, ConfigurationError> {
+    match format {
+        ConfigurationFormat::Json => {
+            serde_json::from_str(content).map_err(ConfigurationError::JsonError)
+        }
+        ConfigurationFormat::Toml => toml::from_str(content).map_err(|e| {
+            let location = e
+                .span()
+                .map(|span| FileLocation::from_offset(content, span.start));
+            ConfigurationError::TomlError(e, location)
+        }),
+        ConfigurationFormat::Yaml => {
+            serde_yaml::from_str(content).map_err(ConfigurationError::YamlError)
+        }
+    }
+}
+
+fn read_content(path: &Path) -> Result<(String, ConfigurationFormat), ConfigurationError> {
+    if !path.exists() {
         return Err(ConfigurationError::FileNotFound);
     }
 
-    let file = File::open(path).map_err(ConfigurationError::FileOpenError)?;
-    let reader = BufReader::new(file);
+    let content = fs::read_to_string(path).map_err(ConfigurationError::FileOpenError)?;
+    Ok((content, ConfigurationFormat::from_path(path)))
+}
+
+// parse_configuration_file only parses the file, the secrets it refers to are not read
+pub fn parse_configuration_file(path: &Path) -> Result<Configuration, ConfigurationError> {
+    let (content, format) = read_content(path)?;
+    parse_content(&content, format)
+}
+
+// read_configuration_file parses the file, reads the secrets it refers to and validates it
+pub fn read_configuration_file(path: &Path) -> Result<Configuration, ConfigurationError> {
+    let (content, format) = read_content(path)?;
+    let mut configuration: Configuration = parse_content(&content, format)?;
+    configuration.resolve_credentials();
+
+    let errors = configuration.validate(&content);
+    if !errors.is_empty() {
+        return Err(ConfigurationError::ValidationFailed(errors));
+    }
 
-    let c = serde_json::from_reader(reader).map_err(ConfigurationError::JsonError)?;
-    Ok(c)
+    // keys serde ignored are most likely typos, they don't prevent the configuration from loading
+    let input: serde_json::Value = parse_content(&content, format)?;
+    let known = serde_json::to_value(&configuration).map_err(ConfigurationError::JsonError)?;
+    configuration.warnings = get_unknown_keys(&input, &known)
+        .into_iter()
+        .map(|key| {
+            let location = find_key_location(&content, &key);
+            Arc::new(ConfigurationError::UnknownKey(key, location))
+        })
+        .collect();
+
+    Ok(configuration)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use chrono::NaiveTime;
+    use std::path::{Path, PathBuf};
 
-    use super::load_configuration;
+    use super::{
+        find_configuration_file, get_candidate_files, get_configuration_diff, load_configuration,
+        read_configuration_file, reload_configuration, Configuration, ConfigurationFormat,
+        FileLocation,
+    };
+    use crate::error::ConfigurationError;
+    use crate::goal::DailyGoal;
 
     #[test]
     fn test_initialize_configuration_some() {
         let file = PathBuf::from("resources/test/mock_configuration.json");
-        let file = file.to_str();
 
-        let result = load_configuration(file);
+        let result = load_configuration(Some(&file));
         assert_eq!(true, result.is_ok());
         let config = result.unwrap().0;
 
@@ -135,7 +684,15 @@ mod tests {
 
         let discord_webhook_url = config.get_discord_webhook_url();
         assert_eq!(true, discord_webhook_url.is_some());
-        assert!(discord_webhook_url.as_ref().unwrap().eq("your-webhook-url"));
+        assert!(discord_webhook_url
+            .as_ref()
+            .unwrap()
+            .eq("https://discord.com/api/webhooks/your-webhook"));
+
+        let http_token = config.get_http_token();
+        assert_eq!(true, http_token.is_some());
+        assert!(http_token.as_ref().unwrap().eq("your-http-api-token"));
+        assert_eq!(config.get_http_port(), 7979);
 
         let work_time = config.get_work_time();
         assert_eq!(true, work_time.is_some());
@@ -144,11 +701,76 @@ mod tests {
         let break_time = config.get_break_time();
         assert_eq!(true, break_time.is_some());
         assert_eq!(break_time.unwrap(), 10);
+
+        assert!(config.get_missed_notification_summary());
+        assert!(config.get_review_prompt());
+        assert_eq!(config.get_daily_goal(), Some(DailyGoal::Pomodoros(8)));
+        assert_eq!(
+            config.get_daily_goal_reminder(),
+            NaiveTime::from_hms_opt(21, 0, 0)
+        );
+
+        let deep = config.get_preset("deep").unwrap();
+        assert_eq!((deep.work, deep.r#break), (50, 10));
+        assert_eq!(config.get_presets().len(), 2);
+        assert!(config.get_preset("standard").is_none());
+    }
+
+    #[test]
+    fn test_reload_configuration() {
+        let file = PathBuf::from("resources/test/mock_configuration.json");
+        let (config, _) = load_configuration(Some(&file)).unwrap();
+        assert!(config.get_path().as_ref().unwrap().ends_with(&file));
+
+        let reloaded = reload_configuration(&config).unwrap();
+        assert_eq!(reloaded.get_path(), config.get_path());
+        assert_eq!(reloaded.get_work_time(), Some(30));
+
+        let (config, _) = load_configuration(None).unwrap();
+        assert!(matches!(
+            reload_configuration(&config),
+            Err(ConfigurationError::NotLoadedFromFile)
+        ));
+
+        // a file which fails to load still is the one to reload
+        let (config, error) = load_configuration(Some(Path::new("wrong_path"))).unwrap();
+        assert!(matches!(error, Some(ConfigurationError::FileNotFound)));
+        assert!(config.get_path().as_ref().unwrap().ends_with("wrong_path"));
+        assert!(matches!(
+            reload_configuration(&config),
+            Err(ConfigurationError::FileNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_reload_fixed_configuration() {
+        let path =
+            std::env::temp_dir().join(format!("pomodoro-reload-test-{}.toml", std::process::id()));
+        std::fs::write(&path, "work_time_default_value = 0\n").unwrap();
+
+        // the server starts with the defaults
+        let (config, error) = load_configuration(Some(&path)).unwrap();
+        assert!(matches!(
+            error,
+            Some(ConfigurationError::ValidationFailed(_))
+        ));
+        assert_eq!(config.get_work_time(), None);
+
+        std::fs::write(&path, "work_time_default_value = 40\n").unwrap();
+        let reloaded = reload_configuration(&config).unwrap();
+        assert_eq!(reloaded.get_work_time(), Some(40));
+        assert_eq!(reloaded.get_path().as_deref(), Some(path.as_path()));
+        assert_eq!(
+            get_configuration_diff(&config, &reloaded),
+            vec!["work_time_default_value: (none) -> 40"]
+        );
+
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
     fn test_initialize_configuration_none() {
-        [PathBuf::from("wrong_path").to_str(), None]
+        [Some(Path::new("wrong_path")), None]
             .into_iter()
             .for_each(|file| {
                 let result = load_configuration(file);
@@ -164,6 +786,10 @@ mod tests {
                 let discord_webhook_url = config.get_discord_webhook_url();
                 assert_eq!(true, discord_webhook_url.is_none());
 
+                let http_token = config.get_http_token();
+                assert_eq!(true, http_token.is_none());
+                assert_eq!(config.get_http_port(), super::DEFAULT_HTTP_PORT);
+
                 let work_time = config.get_work_time();
                 assert_eq!(true, work_time.is_none());
 
@@ -171,4 +797,166 @@ mod tests {
                 assert_eq!(true, break_time.is_none());
             });
     }
+
+    fn assert_same_configuration(a: &Configuration, b: &Configuration) {
+        assert_eq!(a.get_slack_token(), b.get_slack_token());
+        assert_eq!(a.get_slack_channel(), b.get_slack_channel());
+        assert_eq!(a.get_discord_webhook_url(), b.get_discord_webhook_url());
+        assert_eq!(a.get_http_token(), b.get_http_token());
+        assert_eq!(a.get_http_port(), b.get_http_port());
+        assert_eq!(a.get_work_time(), b.get_work_time());
+        assert_eq!(a.get_break_time(), b.get_break_time());
+        assert_eq!(
+            a.get_missed_notification_summary(),
+            b.get_missed_notification_summary()
+        );
+        assert_eq!(a.get_presets(), b.get_presets());
+    }
+
+    #[test]
+    fn test_configuration_formats() {
+        let (json, err) =
+            load_configuration(Some(Path::new("resources/test/mock_configuration.json"))).unwrap();
+        assert!(err.is_none());
+
+        for file in [
+            "resources/test/mock_configuration.toml",
+            "resources/test/mock_configuration.yaml",
+        ] {
+            let (config, err) = load_configuration(Some(Path::new(file))).unwrap();
+            assert!(err.is_none(), "{}: {:?}", file, err);
+            assert_same_configuration(&json, &config);
+        }
+
+        // the printed configuration can be read back
+        for format in [
+            ConfigurationFormat::Json,
+            ConfigurationFormat::Toml,
+            ConfigurationFormat::Yaml,
+        ] {
+            let serialized = json.serialize(format).unwrap();
+            let parsed: Configuration = match format {
+                ConfigurationFormat::Json => serde_json::from_str(&serialized).unwrap(),
+                ConfigurationFormat::Toml => toml::from_str(&serialized).unwrap(),
+                ConfigurationFormat::Yaml => serde_yaml::from_str(&serialized).unwrap(),
+            };
+            assert_same_configuration(&json, &parsed);
+        }
+
+        let redacted = json.get_redacted();
+        assert_eq!(redacted.get_http_token().as_deref(), Some("********"));
+        assert_eq!(redacted.get_slack_channel(), json.get_slack_channel());
+    }
+
+    #[test]
+    fn test_find_configuration_file() {
+        let dir = std::env::temp_dir().join(format!("pomodoro-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(find_configuration_file(&dir), None);
+
+        let candidates = get_candidate_files(&dir);
+        assert!(candidates[0].ends_with("config.toml"));
+
+        std::fs::write(dir.join("config.json"), "{}").unwrap();
+        assert_eq!(find_configuration_file(&dir), Some(dir.join("config.json")));
+
+        // toml is preferred when several files exist
+        std::fs::write(dir.join("config.toml"), "").unwrap();
+        assert_eq!(find_configuration_file(&dir), Some(dir.join("config.toml")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_configuration_diff() {
+        let old: Configuration = toml::from_str(
+            "work_time_default_value = 25\n[http]\nport = 7878\ntoken = \"old-token\"",
+        )
+        .unwrap();
+        let new: Configuration = toml::from_str(
+            "work_time_default_value = 30\nbreak_time_default_value = 10\n[http]\nport = 7878\ntoken = \"new-token\"",
+        )
+        .unwrap();
+
+        assert!(get_configuration_diff(&old, &old).is_empty());
+        assert_eq!(
+            get_configuration_diff(&old, &new),
+            vec![
+                "break_time_default_value: (none) -> 10",
+                "work_time_default_value: 25 -> 30",
+                "http.token: changed",
+            ]
+        );
+    }
+
+    fn read_test_configuration(
+        name: &str,
+        content: &str,
+    ) -> Result<Configuration, ConfigurationError> {
+        let path = std::env::temp_dir().join(format!(
+            "pomodoro-validation-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, content).unwrap();
+        let result = read_configuration_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        result
+    }
+
+    #[test]
+    fn test_configuration_validation() {
+        let config = read_test_configuration(
+            "warning.toml",
+            "work_time_default_value = 25\n[slack]\nchanel = \"a\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.get_work_time(), Some(25));
+        assert_eq!(
+            config.get_warnings()[0].to_string(),
+            "unknown key (slack.chanel) at line 3, column 1"
+        );
+
+        let result = read_test_configuration(
+            "invalid.yaml",
+            "work_time_default_value: 0\ndiscord:\n  webhook_url: your-webhook-url\n",
+        );
+        match result {
+            Err(ConfigurationError::ValidationFailed(errors)) => {
+                assert!(matches!(
+                    errors[0],
+                    ConfigurationError::OutOfRange(_, _, Some(FileLocation { line: 1, column: 1 }))
+                ));
+                assert!(matches!(
+                    errors[1],
+                    ConfigurationError::InvalidUrl(_, _, Some(FileLocation { line: 3, column: 3 }))
+                ));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let e = read_test_configuration(
+            "goal.toml",
+            "[daily_goal]\npomodoros = 8\nminutes = 200\nreminder = \"9pm\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            e.to_string().contains("give either pomodoros or minutes"),
+            "{}",
+            e
+        );
+        assert!(
+            e.to_string().contains("9pm is not a time like 21:00"),
+            "{}",
+            e
+        );
+
+        let e = read_test_configuration("broken.json", "{\n  \"http\": {\n    \"port\": \"a\"\n}")
+            .unwrap_err();
+        assert!(e.to_string().contains("at line 3"), "{}", e);
+
+        let e = read_test_configuration("broken.toml", "[http]\nport = \"a\"\n").unwrap_err();
+        assert!(e.to_string().ends_with("at line 2, column 8"), "{}", e);
+    }
 }
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::ConfigurationError;
+
+const REDACTED: &str = "********";
+// a password manager waiting for an unlock prompt must not hold the configuration forever
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// CredentialSource is where a secret of the configuration comes from, either the value itself
+/// or `{ "env": .. }`, `{ "file": .. }` and `{ "command": .. }`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum CredentialSource {
+    Value(String),
+    Env { env: String },
+    File { file: PathBuf },
+    Command { command: String },
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialSource::Value(_) => write!(f, "configuration file"),
+            CredentialSource::Env { env } => write!(f, "env {}", env),
+            CredentialSource::File { file } => write!(f, "file {}", file.display()),
+            CredentialSource::Command { command } => write!(f, "command `{}`", command),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "CredentialSource", into = "CredentialSource")]
+pub struct Credential {
+    source: CredentialSource,
+    // set by resolve, the secret itself is never serialized
+    value: Option<String>,
+    error: Option<Arc<ConfigurationError>>,
+}
+
+impl From<CredentialSource> for Credential {
+    fn from(source: CredentialSource) -> Self {
+        let value = match &source {
+            CredentialSource::Value(value) => Some(value.clone()),
+            _ => None,
+        };
+
+        Credential {
+            source,
+            value,
+            error: None,
+        }
+    }
+}
+
+impl From<Credential> for CredentialSource {
+    fn from(credential: Credential) -> Self {
+        credential.source
+    }
+}
+
+impl Credential {
+    pub fn get_source(&self) -> &CredentialSource {
+        &self.source
+    }
+
+    pub fn get_value(&self) -> &Option<String> {
+        &self.value
+    }
+
+    pub fn get_error(&self) -> Option<&ConfigurationError> {
+        self.error.as_deref()
+    }
+
+    // resolve reads the secret from its source, the command runs through `sh -c`
+    pub fn resolve(&mut self) {
+        let result = match &self.source {
+            CredentialSource::Value(value) => Ok(value.clone()),
+            CredentialSource::Env { env } => {
+                env::var(env).map_err(|_| ConfigurationError::CredentialEnvNotSet(env.clone()))
+            }
+            CredentialSource::File { file } => fs::read_to_string(file)
+                .map_err(|e| ConfigurationError::CredentialFileError(file.clone(), e)),
+            CredentialSource::Command { command } => run_command(command),
+        };
+
+        // files and command outputs usually end with a newline
+        match result.map(|value| value.trim().to_string()) {
+            Ok(value) if value.is_empty() => {
+                self.value = None;
+                self.error = Some(Arc::new(ConfigurationError::CredentialEmpty(
+                    self.source.to_string(),
+                )));
+            }
+            Ok(value) => {
+                self.value = Some(value);
+                self.error = None;
+            }
+            Err(e) => {
+                debug!("failed to resolve credential from {}: {}", self.source, e);
+                self.value = None;
+                self.error = Some(Arc::new(e));
+            }
+        }
+    }
+
+    // get_redacted keeps where the secret comes from, but hides a value written in the file
+    pub fn get_redacted(&self) -> Credential {
+        match &self.source {
+            CredentialSource::Value(_) => {
+                Credential::from(CredentialSource::Value(String::from(REDACTED)))
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+fn run_command(command: &str) -> Result<String, ConfigurationError> {
+    run_command_with_timeout(command, COMMAND_TIMEOUT)
+}
+
+// run_command_with_timeout kills the command, and whatever it started, once the timeout is over
+fn run_command_with_timeout(
+    command: &str,
+    timeout: Duration,
+) -> Result<String, ConfigurationError> {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| ConfigurationError::CredentialCommandError(command.to_string(), e))?;
+    let pid = child.id() as libc::pid_t;
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(child.wait_with_output());
+    });
+    let output = match rx.recv_timeout(timeout) {
+        Ok(output) => output
+            .map_err(|e| ConfigurationError::CredentialCommandError(command.to_string(), e))?,
+        Err(_) => {
+            // SAFETY: kill has no memory safety preconditions, the group is the one we started
+            unsafe { libc::kill(-pid, libc::SIGKILL) };
+            return Err(ConfigurationError::CredentialCommandTimeout(
+                command.to_string(),
+                timeout.as_secs(),
+            ));
+        }
+    };
+
+    if !output.status.success() {
+        return Err(ConfigurationError::CredentialCommandFailed(
+            command.to_string(),
+            output.status,
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{run_command_with_timeout, Credential, CredentialSource};
+    use crate::error::ConfigurationError;
+
+    fn resolve(json: &str) -> Credential {
+        let mut credential: Credential = serde_json::from_str(json).unwrap();
+        credential.resolve();
+        credential
+    }
+
+    #[test]
+    fn test_credential_sources() {
+        let credential = resolve(r#""plain-token""#);
+        assert_eq!(credential.get_value().as_deref(), Some("plain-token"));
+        assert_eq!(credential.get_source().to_string(), "configuration file");
+
+        std::env::set_var("POMODORO_TEST_CREDENTIAL", "env-token");
+        let credential = resolve(r#"{"env": "POMODORO_TEST_CREDENTIAL"}"#);
+        assert_eq!(credential.get_value().as_deref(), Some("env-token"));
+        assert_eq!(
+            credential.get_source().to_string(),
+            "env POMODORO_TEST_CREDENTIAL"
+        );
+
+        let file =
+            std::env::temp_dir().join(format!("pomodoro-credential-test-{}", std::process::id()));
+        std::fs::write(&file, "file-token\n").unwrap();
+        let credential = resolve(&format!(r#"{{"file": {:?}}}"#, file));
+        assert_eq!(credential.get_value().as_deref(), Some("file-token"));
+        std::fs::remove_file(&file).unwrap();
+
+        let credential = resolve(r#"{"command": "echo command-token"}"#);
+        assert_eq!(credential.get_value().as_deref(), Some("command-token"));
+    }
+
+    #[test]
+    fn test_credential_errors() {
+        let credential = resolve(r#"{"env": "POMODORO_TEST_CREDENTIAL_NOT_SET"}"#);
+        assert!(credential.get_value().is_none());
+        assert!(matches!(
+            credential.get_error(),
+            Some(ConfigurationError::CredentialEnvNotSet(_))
+        ));
+
+        let credential = resolve(r#"{"file": "/nonexistent/pomodoro-token"}"#);
+        assert!(matches!(
+            credential.get_error(),
+            Some(ConfigurationError::CredentialFileError(_, _))
+        ));
+
+        let credential = resolve(r#"{"command": "exit 3"}"#);
+        assert!(matches!(
+            credential.get_error(),
+            Some(ConfigurationError::CredentialCommandFailed(_, _))
+        ));
+
+        let credential = resolve(r#"{"command": "true"}"#);
+        assert!(matches!(
+            credential.get_error(),
+            Some(ConfigurationError::CredentialEmpty(_))
+        ));
+    }
+
+    #[test]
+    fn test_credential_command_timeout() {
+        let started_at = Instant::now();
+        let result = run_command_with_timeout("sleep 5; echo late", Duration::from_millis(200));
+        assert!(matches!(
+            result,
+            Err(ConfigurationError::CredentialCommandTimeout(_, _))
+        ));
+        assert!(started_at.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_redacted_credential() {
+        let credential = resolve(r#""plain-token""#);
+        let json = serde_json::to_string(&credential.get_redacted()).unwrap();
+        assert_eq!(json, r#""********""#);
+
+        let credential = resolve(r#"{"command": "echo command-token"}"#);
+        let json = serde_json::to_string(&credential.get_redacted()).unwrap();
+        assert_eq!(json, r#"{"command":"echo command-token"}"#);
+        assert_eq!(
+            credential.get_redacted().get_source(),
+            &CredentialSource::Command {
+                command: String::from("echo command-token")
+            }
+        );
+    }
+}
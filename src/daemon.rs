@@ -0,0 +1,339 @@
+use clap::ArgMatches;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::command::util;
+use crate::error::DaemonError;
+use crate::{instance, ipc};
+
+const SPAWN_SERVER_ENV: &str = "POMODORO_SPAWN_SERVER";
+const SPAWN_TIMEOUT_SECS: u64 = 15;
+
+/// Options of `pomodoro daemon`, which runs the server without the interactive prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonOptions {
+    pub log_file: Option<PathBuf>,
+    pub pid_file: PathBuf,
+}
+
+impl DaemonOptions {
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        DaemonOptions {
+            log_file: matches.get_one::<String>("log-file").map(PathBuf::from),
+            pid_file: matches
+                .get_one::<String>("pid-file")
+                .map(PathBuf::from)
+                .unwrap_or_else(get_default_pid_file),
+        }
+    }
+}
+
+// the default pid and log files are named after the instance and live next to its sockets,
+// in a directory no other user can write to
+pub fn get_default_pid_file() -> PathBuf {
+    instance::get()
+        .get_socket_dir()
+        .join(format!("{}.pid", instance::get().get_prefix()))
+}
+
+fn get_spawned_log_file() -> PathBuf {
+    instance::get()
+        .get_socket_dir()
+        .join(format!("{}.log", instance::get().get_prefix()))
+}
+
+// open_log_file refuses a symlink in place of the log file, so logs can't be redirected elsewhere
+pub fn open_log_file(path: &Path) -> Result<File, DaemonError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(DaemonError::LogFileFailed)?;
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+        .map_err(DaemonError::LogFileFailed)
+}
+
+/// PidFile holds the pid file of the running daemon and removes it when dropped
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> Result<Self, DaemonError> {
+        if let Some(pid) = read_pid(path) {
+            if is_process_alive(pid) {
+                return Err(DaemonError::AlreadyRunning(pid));
+            }
+            debug!("removing stale pid file of process ({})", pid);
+        }
+
+        // a stale file (or a symlink in its place) is removed, the new one must not exist yet
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                return Err(DaemonError::PidFileFailed(e))
+            }
+            _ => {}
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(DaemonError::PidFileFailed)?;
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o644)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(path)
+            .map_err(DaemonError::PidFileFailed)?;
+        writeln!(file, "{}", std::process::id()).map_err(DaemonError::PidFileFailed)?;
+
+        Ok(PidFile {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            debug!("failed to remove pid file {:?}: {}", self.path, e);
+        }
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse::<u32>().ok()
+}
+
+// without procfs we can't tell, so an existing pid file is considered stale
+fn is_process_alive(pid: u32) -> bool {
+    pid != std::process::id() && Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// How a client starts the server when none is running, see `--spawn-server`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpawnOptions {
+    pub enabled: bool,
+    pub config: Option<PathBuf>,
+}
+
+impl SpawnOptions {
+    // the env variable lets shell profiles opt in once for every command
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let sub_matches = util::get_leaf_matches(matches);
+        let enabled = sub_matches.get_flag("spawn-server")
+            || env::var(SPAWN_SERVER_ENV)
+                .map(|value| is_truthy(&value))
+                .unwrap_or(false);
+
+        SpawnOptions {
+            enabled,
+            config: sub_matches
+                .get_one::<String>("server-config")
+                .map(PathBuf::from),
+        }
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// Starts a detached daemon when no server answers Ping, and waits until it does
+pub async fn ensure_server_running(options: &SpawnOptions) -> Result<(), DaemonError> {
+    if !options.enabled || ipc::is_server_running().await {
+        return Ok(());
+    }
+
+    spawn_detached_server(options.config.as_deref())?;
+
+    let started_at = Instant::now();
+    while started_at.elapsed() < Duration::from_secs(SPAWN_TIMEOUT_SECS) {
+        sleep(Duration::from_millis(200)).await;
+        if ipc::is_server_running().await {
+            debug!("spawned server is ready after {:?}", started_at.elapsed());
+            return Ok(());
+        }
+    }
+
+    Err(DaemonError::StartTimeout(SPAWN_TIMEOUT_SECS))
+}
+
+fn spawn_detached_server(config: Option<&Path>) -> Result<(), DaemonError> {
+    let executable = env::current_exe().map_err(DaemonError::SpawnFailed)?;
+    ipc::prepare_instance_dir().map_err(DaemonError::SpawnFailed)?;
+    let log_file = get_spawned_log_file();
+
+    let mut command = Command::new(executable);
+    command.arg("daemon").arg("--log-file").arg(&log_file);
+    for (flag, value) in instance::get().get_flags() {
+        command.arg(format!("--{}", flag)).arg(value);
+    }
+    if let Some(config) = config {
+        let config = env::current_dir()
+            .map_err(DaemonError::SpawnFailed)?
+            .join(config);
+        command.arg("--config").arg(config);
+    }
+
+    // own process group, so ctrl-c in the client terminal doesn't reach the server
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(DaemonError::SpawnFailed)?;
+    info!(
+        "spawned server (pid: {}), logs are written to {:?}",
+        child.id(),
+        log_file
+    );
+
+    Ok(())
+}
+
+/// Resolves when the server is asked to stop, with SIGTERM (systemd, kill) or SIGINT
+pub async fn wait_for_shutdown_signal() -> Result<(), DaemonError> {
+    let mut sigterm = signal(SignalKind::terminate()).map_err(DaemonError::SignalFailed)?;
+
+    tokio::select! {
+        _ = sigterm.recv() => info!("received SIGTERM, shutting down"),
+        result = tokio::signal::ctrl_c() => {
+            result.map_err(DaemonError::SignalFailed)?;
+            info!("received SIGINT, shutting down");
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders a systemd user unit running the daemon with the current binary and given flags
+pub fn generate_systemd_unit(executable: &Path, flags: &[(&str, String)]) -> String {
+    let mut exec_start = format!("{} daemon", executable.display());
+    for (flag, value) in flags {
+        exec_start.push_str(&format!(" --{} {}", flag, value));
+    }
+
+    format!(
+        "[Unit]
+Description=rust-cli-pomodoro daemon
+After=graphical-session.target
+
+[Service]
+Type=simple
+ExecStart={}
+Restart=on-failure
+KillSignal=SIGTERM
+
+[Install]
+WantedBy=default.target
+",
+        exec_start
+    )
+}
+
+pub fn get_systemd_unit(matches: &ArgMatches) -> io::Result<String> {
+    let executable = env::current_exe()?;
+
+    // paths in the unit must be absolute, systemd doesn't know our working directory
+    let mut flags = instance::get().get_flags();
+    for flag in ["config", "log-file", "pid-file"] {
+        if let Some(path) = matches.get_one::<String>(flag) {
+            flags.push((flag, env::current_dir()?.join(path).display().to_string()));
+        }
+    }
+
+    Ok(generate_systemd_unit(&executable, &flags))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{generate_systemd_unit, is_truthy, open_log_file, PidFile};
+    use crate::error::DaemonError;
+
+    #[test]
+    fn test_pid_file() {
+        let path = std::env::temp_dir().join(format!(
+            "rust-cli-pomodoro-test-{}.pid",
+            uuid::Uuid::new_v4()
+        ));
+
+        let pid_file = PidFile::create(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.trim(), std::process::id().to_string());
+
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // pid of a finished process is stale and gets replaced
+        std::fs::write(&path, "4294967295").unwrap();
+        let pid_file = PidFile::create(&path);
+        assert!(!matches!(pid_file, Err(DaemonError::AlreadyRunning(_))));
+        drop(pid_file);
+
+        // a symlink in place of the pid file is replaced, its target is left alone
+        let target = path.with_extension("target");
+        std::os::unix::fs::symlink(&target, &path).unwrap();
+        let pid_file = PidFile::create(&path).unwrap();
+        assert!(!target.exists());
+        assert!(!std::fs::symlink_metadata(&path).unwrap().is_symlink());
+        drop(pid_file);
+    }
+
+    #[test]
+    fn test_open_log_file() {
+        let path = std::env::temp_dir().join(format!(
+            "rust-cli-pomodoro-test-{}.log",
+            uuid::Uuid::new_v4()
+        ));
+        assert!(open_log_file(&path).is_ok());
+        std::fs::remove_file(&path).unwrap();
+
+        std::os::unix::fs::symlink(std::env::temp_dir().join("elsewhere.log"), &path).unwrap();
+        assert!(matches!(
+            open_log_file(&path),
+            Err(DaemonError::LogFileFailed(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_is_truthy() {
+        assert!(is_truthy("1"));
+        assert!(is_truthy(" True"));
+        assert!(!is_truthy("0"));
+        assert!(!is_truthy(""));
+    }
+
+    #[test]
+    fn test_generate_systemd_unit() {
+        let unit = generate_systemd_unit(
+            Path::new("/usr/bin/pomodoro"),
+            &[
+                ("instance", String::from("work")),
+                ("config", String::from("/home/user/.config/pomodoro.json")),
+            ],
+        );
+        assert!(unit.contains(
+            "ExecStart=/usr/bin/pomodoro daemon --instance work --config /home/user/.config/pomodoro.json\n"
+        ));
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+}
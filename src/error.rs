@@ -2,7 +2,12 @@ use bincode::error::{DecodeError, EncodeError};
 use notify_rust::error::Error as NotifyRustError;
 use reqwest::Error as RequestError;
 use serde_json::error::Error as SerdeJsonError;
+use std::path::PathBuf;
+use std::process::ExitStatus;
 use std::{error::Error, fmt, io, result};
+use uuid::Uuid;
+
+use crate::config_validation::FileLocation;
 
 pub type NotifyResult = result::Result<(), NotificationError>;
 
@@ -63,12 +68,40 @@ pub enum ConfigurationError {
     FileNotFound,
     FileOpenError(io::Error),
     JsonError(SerdeJsonError),
+    TomlError(toml::de::Error, Option<FileLocation>),
+    TomlSerializeError(toml::ser::Error),
+    YamlError(serde_yaml::Error),
     SlackConfigNotFound,
     DiscordConfigNotFound,
+    HttpConfigNotFound,
     UnspecifiedWorkTime,
     UnspecifiedBreakTime,
     LoadFail(io::Error),
-    // config json wrong format?
+    NotLoadedFromFile,
+    // the file given to `config validate` can't be used
+    Invalid(PathBuf),
+    CredentialEnvNotSet(String),
+    CredentialFileError(PathBuf, io::Error),
+    CredentialCommandError(String, io::Error),
+    CredentialCommandFailed(String, ExitStatus),
+    CredentialCommandTimeout(String, u64),
+    CredentialEmpty(String),
+    // the reloaded configuration refers to secrets which can't be read
+    CredentialUnavailable(String),
+    // a key serde ignored, reported as a warning
+    UnknownKey(String, Option<FileLocation>),
+    OutOfRange(String, String, Option<FileLocation>),
+    InvalidUrl(String, String, Option<FileLocation>),
+    InvalidTime(String, String, Option<FileLocation>),
+    InvalidPresetName(String, Option<FileLocation>),
+    ValidationFailed(Vec<ConfigurationError>),
+}
+
+fn write_location(f: &mut fmt::Formatter, location: &Option<FileLocation>) -> fmt::Result {
+    match location {
+        Some(location) => write!(f, " at {}", location),
+        None => Ok(()),
+    }
 }
 
 impl fmt::Display for ConfigurationError {
@@ -76,16 +109,94 @@ impl fmt::Display for ConfigurationError {
         match self {
             ConfigurationError::FileNotFound => write!(f, "can not find configuration file"),
             ConfigurationError::FileOpenError(_) => write!(f, "failed to open the file"),
-            ConfigurationError::JsonError(_) => write!(f, "failed to deserialize json"),
+            ConfigurationError::JsonError(e) => write!(
+                f,
+                "failed to deserialize json at line {}, column {}",
+                e.line(),
+                e.column()
+            ),
+            ConfigurationError::TomlError(e, location) => {
+                write!(f, "failed to deserialize toml: {}", e.message())?;
+                write_location(f, location)
+            }
+            ConfigurationError::TomlSerializeError(_) => write!(f, "failed to serialize toml"),
+            ConfigurationError::YamlError(e) => {
+                write!(f, "failed to deserialize yaml")?;
+                match e.location() {
+                    Some(location) => write!(
+                        f,
+                        " at line {}, column {}",
+                        location.line(),
+                        location.column()
+                    ),
+                    None => Ok(()),
+                }
+            }
             ConfigurationError::SlackConfigNotFound => {
-                write!(f, "can not find slack config in json")
+                write!(f, "can not find slack config")
             }
             ConfigurationError::DiscordConfigNotFound => {
-                write!(f, "can not find discord config in json")
+                write!(f, "can not find discord config")
+            }
+            ConfigurationError::HttpConfigNotFound => {
+                write!(f, "can not find http token")
             }
             ConfigurationError::UnspecifiedWorkTime => write!(f, "not specified"),
             ConfigurationError::UnspecifiedBreakTime => write!(f, "not specified"),
             ConfigurationError::LoadFail(e) => write!(f, "failed to load: {}", e),
+            ConfigurationError::NotLoadedFromFile => {
+                write!(f, "server was started without configuration file")
+            }
+            ConfigurationError::Invalid(path) => {
+                write!(f, "configuration file ({}) is invalid", path.display())
+            }
+            ConfigurationError::CredentialEnvNotSet(name) => {
+                write!(f, "environment variable ({}) is not set", name)
+            }
+            ConfigurationError::CredentialFileError(path, _) => {
+                write!(f, "failed to read credential file ({})", path.display())
+            }
+            ConfigurationError::CredentialCommandError(command, _) => {
+                write!(f, "failed to run credential command ({})", command)
+            }
+            ConfigurationError::CredentialCommandFailed(command, status) => {
+                write!(f, "credential command ({}) failed with {}", command, status)
+            }
+            ConfigurationError::CredentialCommandTimeout(command, secs) => {
+                write!(
+                    f,
+                    "credential command ({}) did not finish within {} seconds",
+                    command, secs
+                )
+            }
+            ConfigurationError::CredentialEmpty(source) => {
+                write!(f, "credential from {} is empty", source)
+            }
+            ConfigurationError::CredentialUnavailable(errors) => {
+                write!(f, "failed to read credentials, {}", errors)
+            }
+            ConfigurationError::UnknownKey(key, location) => {
+                write!(f, "unknown key ({})", key)?;
+                write_location(f, location)
+            }
+            ConfigurationError::OutOfRange(key, reason, location)
+            | ConfigurationError::InvalidUrl(key, reason, location)
+            | ConfigurationError::InvalidTime(key, reason, location) => {
+                write!(f, "invalid {}: {}", key, reason)?;
+                write_location(f, location)
+            }
+            ConfigurationError::InvalidPresetName(name, location) => {
+                write!(
+                    f,
+                    "invalid preset name ({}), use letters, digits, - and _",
+                    name
+                )?;
+                write_location(f, location)
+            }
+            ConfigurationError::ValidationFailed(errors) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", errors.join("\n"))
+            }
         }
     }
 }
@@ -96,11 +207,30 @@ impl std::error::Error for ConfigurationError {
             ConfigurationError::FileNotFound => None,
             ConfigurationError::FileOpenError(ref e) => Some(e),
             ConfigurationError::JsonError(ref e) => Some(e),
+            ConfigurationError::TomlError(ref e, _) => Some(e),
+            ConfigurationError::TomlSerializeError(ref e) => Some(e),
+            ConfigurationError::YamlError(ref e) => Some(e),
             ConfigurationError::SlackConfigNotFound => None,
             ConfigurationError::DiscordConfigNotFound => None,
+            ConfigurationError::HttpConfigNotFound => None,
             ConfigurationError::UnspecifiedWorkTime => None,
             ConfigurationError::UnspecifiedBreakTime => None,
             ConfigurationError::LoadFail(ref e) => Some(e),
+            ConfigurationError::NotLoadedFromFile => None,
+            ConfigurationError::Invalid(_) => None,
+            ConfigurationError::CredentialEnvNotSet(_) => None,
+            ConfigurationError::CredentialFileError(_, ref e) => Some(e),
+            ConfigurationError::CredentialCommandError(_, ref e) => Some(e),
+            ConfigurationError::CredentialCommandFailed(_, _) => None,
+            ConfigurationError::CredentialCommandTimeout(_, _) => None,
+            ConfigurationError::CredentialEmpty(_) => None,
+            ConfigurationError::CredentialUnavailable(_) => None,
+            ConfigurationError::UnknownKey(_, _) => None,
+            ConfigurationError::OutOfRange(_, _, _) => None,
+            ConfigurationError::InvalidUrl(_, _, _) => None,
+            ConfigurationError::InvalidTime(_, _, _) => None,
+            ConfigurationError::InvalidPresetName(_, _) => None,
+            ConfigurationError::ValidationFailed(_) => None,
         }
     }
 }
@@ -112,16 +242,19 @@ pub enum UdsHandlerError {
     SocketError(std::io::Error),
     EncodeFailed(EncodeError),
     DecodeFailed(DecodeError),
+    // the server stopped reading requests
+    ChannelClosed,
 }
 
 impl fmt::Display for UdsHandlerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             UdsHandlerError::NoSubcommand => write!(f, "subcommand is not present at runtime"),
-            UdsHandlerError::ParseError(_) => write!(f, "failed to parse"),
+            UdsHandlerError::ParseError(e) => write!(f, "failed to parse: {}", e),
             UdsHandlerError::SocketError(_) => write!(f, "failed to handle socket method"),
             UdsHandlerError::EncodeFailed(_) => write!(f, "failed to encode message"),
             UdsHandlerError::DecodeFailed(_) => write!(f, "failed to decode message"),
+            UdsHandlerError::ChannelClosed => write!(f, "server stopped handling requests"),
         }
     }
 }
@@ -134,6 +267,7 @@ impl std::error::Error for UdsHandlerError {
             UdsHandlerError::SocketError(ref e) => Some(e),
             UdsHandlerError::EncodeFailed(ref e) => Some(e),
             UdsHandlerError::DecodeFailed(ref e) => Some(e),
+            UdsHandlerError::ChannelClosed => None,
         }
     }
 }
@@ -172,7 +306,16 @@ pub enum UserInputHandlerError {
     ParseError(ParseError),
     CommandMatchError(clap::Error),
     NotificationError(NotificationError),
-    SledError(sled::Error),
+    StoreError(StoreError),
+    ConfigurationError(ConfigurationError),
+    UnknownTask(u64),
+    TaskAlreadyDone(u64),
+    NotWorking,
+    NotRunning,
+    NotPaused,
+    UnknownNotification(String),
+    AmbiguousNotification(String),
+    WorkNotOver(Uuid),
 }
 
 impl fmt::Display for UserInputHandlerError {
@@ -186,15 +329,37 @@ impl fmt::Display for UserInputHandlerError {
                 write!(f, "failed to get matches: {}", e.render().ansi())
             }
             UserInputHandlerError::NotificationError(e) => write!(f, "{}", e),
-            UserInputHandlerError::SledError(e) => write!(f, "{}", e),
+            UserInputHandlerError::StoreError(e) => write!(f, "{}", e),
+            UserInputHandlerError::ConfigurationError(e) => {
+                write!(f, "failed to reload configuration: {}", e)
+            }
+            UserInputHandlerError::UnknownTask(id) => write!(f, "task ({}) does not exist", id),
+            UserInputHandlerError::TaskAlreadyDone(id) => {
+                write!(f, "task ({}) is already done", id)
+            }
+            UserInputHandlerError::NotWorking => write!(f, "no pomodoro is in its work phase"),
+            UserInputHandlerError::NotRunning => write!(f, "no pomodoro is running"),
+            UserInputHandlerError::NotPaused => write!(f, "no pomodoro is paused"),
+            UserInputHandlerError::UnknownNotification(id) => {
+                write!(f, "notification ({}) does not exist", id)
+            }
+            UserInputHandlerError::AmbiguousNotification(id) => {
+                write!(
+                    f,
+                    "several notifications start with ({}), give more of the id",
+                    id
+                )
+            }
+            UserInputHandlerError::WorkNotOver(id) => {
+                write!(f, "the work time of notification ({}) is not over yet", id)
+            }
         }
     }
 }
 
-// Implement the From trait for UserInputHandlerError to convert from sled::Error
-impl From<sled::Error> for UserInputHandlerError {
-    fn from(error: sled::Error) -> Self {
-        UserInputHandlerError::SledError(error)
+impl From<StoreError> for UserInputHandlerError {
+    fn from(error: StoreError) -> Self {
+        UserInputHandlerError::StoreError(error)
     }
 }
 
@@ -205,7 +370,97 @@ impl std::error::Error for UserInputHandlerError {
             UserInputHandlerError::ParseError(ref e) => Some(e),
             UserInputHandlerError::CommandMatchError(ref e) => Some(e),
             UserInputHandlerError::NotificationError(ref e) => Some(e),
-            UserInputHandlerError::SledError(ref e) => Some(e),
+            UserInputHandlerError::StoreError(ref e) => Some(e),
+            UserInputHandlerError::ConfigurationError(ref e) => Some(e),
+            UserInputHandlerError::UnknownTask(_) => None,
+            UserInputHandlerError::TaskAlreadyDone(_) => None,
+            UserInputHandlerError::NotWorking => None,
+            UserInputHandlerError::NotRunning => None,
+            UserInputHandlerError::NotPaused => None,
+            UserInputHandlerError::UnknownNotification(_) => None,
+            UserInputHandlerError::AmbiguousNotification(_) => None,
+            UserInputHandlerError::WorkNotOver(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    OpenFailed(PathBuf, sled::Error),
+    Sled(sled::Error),
+    EncodeFailed(SerdeJsonError),
+    // a stored value which can't be read back, the key is kept to find it
+    DecodeFailed(String, SerdeJsonError),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::OpenFailed(path, e) => {
+                write!(f, "failed to open database ({}): {}", path.display(), e)
+            }
+            StoreError::Sled(e) => write!(f, "database error: {}", e),
+            StoreError::EncodeFailed(e) => write!(f, "failed to encode notification: {}", e),
+            StoreError::DecodeFailed(key, e) => {
+                write!(f, "failed to decode stored notification ({}): {}", key, e)
+            }
+        }
+    }
+}
+
+impl From<sled::Error> for StoreError {
+    fn from(error: sled::Error) -> Self {
+        StoreError::Sled(error)
+    }
+}
+
+impl std::error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StoreError::OpenFailed(_, ref e) => Some(e),
+            StoreError::Sled(ref e) => Some(e),
+            StoreError::EncodeFailed(ref e) => Some(e),
+            StoreError::DecodeFailed(_, ref e) => Some(e),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DaemonError {
+    AlreadyRunning(u32),
+    PidFileFailed(io::Error),
+    LogFileFailed(io::Error),
+    SignalFailed(io::Error),
+    SpawnFailed(io::Error),
+    StartTimeout(u64),
+}
+
+impl fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DaemonError::AlreadyRunning(pid) => {
+                write!(f, "daemon is already running with pid ({})", pid)
+            }
+            DaemonError::PidFileFailed(e) => write!(f, "failed to write pid file: {}", e),
+            DaemonError::LogFileFailed(e) => write!(f, "failed to open log file: {}", e),
+            DaemonError::SignalFailed(e) => write!(f, "failed to listen for signals: {}", e),
+            DaemonError::SpawnFailed(e) => write!(f, "failed to spawn server: {}", e),
+            DaemonError::StartTimeout(secs) => {
+                write!(f, "spawned server did not answer within {} seconds", secs)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DaemonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DaemonError::AlreadyRunning(_) => None,
+            DaemonError::PidFileFailed(ref e) => Some(e),
+            DaemonError::LogFileFailed(ref e) => Some(e),
+            DaemonError::SignalFailed(ref e) => Some(e),
+            DaemonError::SpawnFailed(ref e) => Some(e),
+            DaemonError::StartTimeout(_) => None,
         }
     }
 }
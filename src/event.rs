@@ -0,0 +1,135 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::sled_databbase::NotificationSled;
+use crate::status::Phase;
+
+// subscribers lagging behind more than this many events lose the oldest ones
+const EVENT_BUS_CAPACITY: usize = 64;
+
+pub type EventSender = broadcast::Sender<Event>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Created,
+    Started,
+    PhaseChanged,
+    Paused,
+    Resumed,
+    Completed,
+    Deleted,
+}
+
+/// Event published on the server bus and streamed to `watch` clients as a json line.
+/// `phase` is the phase the pomodoro is in right after the event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Event {
+    #[serde(rename = "event")]
+    pub kind: EventKind,
+    pub id: Uuid,
+    pub phase: Phase,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub at: DateTime<Utc>,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, notification: &NotificationSled, phase: Phase) -> Self {
+        Event {
+            kind,
+            id: notification.get_id(),
+            phase,
+            description: notification.description.clone(),
+            project: notification.project.clone(),
+            tags: notification.tags.clone(),
+            at: Utc::now(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // serializing plain fields can not fail
+        serde_json::to_string(self).unwrap_or_else(|_| String::from("{}"))
+    }
+}
+
+pub fn create_event_bus() -> EventSender {
+    let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+    tx
+}
+
+pub fn publish(events: &EventSender, event: Event) {
+    debug!("publish event: {:?}", event);
+    // send fails only when nobody is subscribed, which is fine
+    let _ = events.send(event);
+}
+
+// get_current_phase returns the phase of a started pomodoro at the given time
+pub fn get_current_phase(notification: &NotificationSled, now: DateTime<Utc>) -> Phase {
+    if notification.is_paused() {
+        Phase::Paused
+    } else if notification.is_working(now) {
+        Phase::Work
+    } else {
+        Phase::Break
+    }
+}
+
+pub fn get_starting_phase(notification: &NotificationSled) -> Phase {
+    if notification.work_seconds > 0 {
+        Phase::Work
+    } else {
+        Phase::Break
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{create_event_bus, publish, Event, EventKind};
+    use crate::sled_databbase::NotificationSled;
+    use crate::status::Phase;
+
+    #[test]
+    fn test_event_json() {
+        let mut notification =
+            NotificationSled::new(String::from("write"), 25 * 60, 5 * 60, Utc::now());
+        notification.tags = vec![String::from("client")];
+        let event = Event::new(EventKind::PhaseChanged, &notification, Phase::Break);
+
+        let json = event.to_json();
+        assert!(json.contains("\"event\":\"phase_changed\""));
+        assert!(json.contains("\"phase\":\"break\""));
+        assert!(json.contains("\"tags\":[\"client\"]"));
+        assert!(!json.contains("\"project\""));
+        assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), event);
+    }
+
+    #[tokio::test]
+    async fn test_publish() {
+        let events = create_event_bus();
+        let notification =
+            NotificationSled::new(String::from("write"), 25 * 60, 5 * 60, Utc::now());
+
+        // publishing without subscribers is not an error
+        publish(
+            &events,
+            Event::new(EventKind::Created, &notification, Phase::Idle),
+        );
+
+        let mut rx = events.subscribe();
+        publish(
+            &events,
+            Event::new(EventKind::Started, &notification, Phase::Work),
+        );
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.kind, EventKind::Started);
+        assert_eq!(event.id, notification.get_id());
+    }
+}
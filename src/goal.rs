@@ -0,0 +1,359 @@
+use chrono::{prelude::*, Days};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::command::util;
+use crate::sled_databbase::NotificationSled;
+
+// a day with any completed pomodoro counts when no goal is given
+pub const DEFAULT_DAILY_GOAL: DailyGoal = DailyGoal::Pomodoros(1);
+
+/// What a day needs to count in a streak.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DailyGoal {
+    Pomodoros(u16),
+    Minutes(u16),
+}
+
+impl DailyGoal {
+    // parse reads a number of pomodoros like `4`, or a focus time like `90m` or `2h`
+    pub fn parse(goal: &str) -> Result<Self, String> {
+        let error = || format!("invalid goal ({}), use e.g. 4 or 2h", goal);
+
+        let goal = match goal.parse::<u16>() {
+            Ok(pomodoros) => DailyGoal::Pomodoros(pomodoros),
+            Err(_) => {
+                let seconds = util::parse_duration(goal).map_err(|_| error())?;
+                DailyGoal::Minutes(u16::try_from(seconds / 60).map_err(|_| error())?)
+            }
+        };
+
+        match goal {
+            DailyGoal::Pomodoros(0) | DailyGoal::Minutes(0) => Err(error()),
+            goal => Ok(goal),
+        }
+    }
+
+    // to_arg writes the goal back the way `parse` reads it
+    pub fn to_arg(self) -> String {
+        match self {
+            DailyGoal::Pomodoros(pomodoros) => pomodoros.to_string(),
+            DailyGoal::Minutes(minutes) => format!("{}m", minutes),
+        }
+    }
+
+    pub fn is_met(&self, day: &DayFocus) -> bool {
+        GoalProgress::new(*self, day).is_reached()
+    }
+
+    fn get_target(&self) -> u16 {
+        match self {
+            DailyGoal::Pomodoros(target) | DailyGoal::Minutes(target) => *target,
+        }
+    }
+}
+
+impl fmt::Display for DailyGoal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DailyGoal::Pomodoros(1) => write!(f, "1 pomodoro"),
+            DailyGoal::Pomodoros(pomodoros) => write!(f, "{} pomodoros", pomodoros),
+            DailyGoal::Minutes(minutes) => write!(f, "{} focus minutes", minutes),
+        }
+    }
+}
+
+/// How far a day is on the way to the daily goal.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GoalProgress {
+    pub goal: DailyGoal,
+    // completed pomodoros or focus minutes, like the goal
+    pub done: u64,
+}
+
+impl GoalProgress {
+    pub fn new(goal: DailyGoal, day: &DayFocus) -> Self {
+        let done = match goal {
+            DailyGoal::Pomodoros(_) => day.completed as u64,
+            DailyGoal::Minutes(_) => day.focus_minutes,
+        };
+
+        GoalProgress { goal, done }
+    }
+
+    // today counts the pomodoros started on the local day of now
+    pub fn today<'a>(
+        goal: DailyGoal,
+        notifications: impl IntoIterator<Item = &'a NotificationSled>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let today = now.with_timezone(&Local).date_naive();
+        let day = get_day_focus(notifications, now)
+            .remove(&today)
+            .unwrap_or_else(|| DayFocus::new(today));
+
+        GoalProgress::new(goal, &day)
+    }
+
+    // reached_with returns the progress when the given notification is the one reaching the goal
+    pub fn reached_with(
+        goal: DailyGoal,
+        notifications: &[NotificationSled],
+        id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        let progress = GoalProgress::today(goal, notifications, now);
+        let before = GoalProgress::today(goal, notifications.iter().filter(|n| n.id != id), now);
+
+        (progress.is_reached() && !before.is_reached()).then_some(progress)
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.done >= self.goal.get_target() as u64
+    }
+
+    // get_short writes the progress like `3/8` or `75/200m`
+    pub fn get_short(&self) -> String {
+        match self.goal {
+            DailyGoal::Pomodoros(target) => format!("{}/{}", self.done, target),
+            DailyGoal::Minutes(target) => format!("{}/{}m", self.done, target),
+        }
+    }
+}
+
+impl fmt::Display for GoalProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.goal {
+            DailyGoal::Pomodoros(target) => write!(f, "{}/{} pomodoros", self.done, target),
+            DailyGoal::Minutes(target) => write!(f, "{}/{} focus minutes", self.done, target),
+        }
+    }
+}
+
+/// Completed pomodoros of a local day.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DayFocus {
+    pub date: NaiveDate,
+    pub completed: usize,
+    pub focus_minutes: u64,
+}
+
+impl DayFocus {
+    pub fn new(date: NaiveDate) -> Self {
+        DayFocus {
+            date,
+            completed: 0,
+            focus_minutes: 0,
+        }
+    }
+}
+
+// get_day_focus sums the completed pomodoros by the local day they started
+pub fn get_day_focus<'a>(
+    notifications: impl IntoIterator<Item = &'a NotificationSled>,
+    now: DateTime<Utc>,
+) -> BTreeMap<NaiveDate, DayFocus> {
+    let mut seconds: BTreeMap<NaiveDate, (usize, u64)> = BTreeMap::new();
+    for notification in notifications.into_iter().filter(|n| n.is_work_done(now)) {
+        let date = notification
+            .get_start_at()
+            .with_timezone(&Local)
+            .date_naive();
+        let total = seconds.entry(date).or_default();
+        total.0 += 1;
+        total.1 += notification.work_seconds as u64;
+    }
+
+    seconds
+        .into_iter()
+        .map(|(date, (completed, seconds))| {
+            let focus_minutes = (seconds + 30) / 60;
+            let day = DayFocus {
+                date,
+                completed,
+                focus_minutes,
+            };
+            (date, day)
+        })
+        .collect()
+}
+
+// get_next_reminder returns the next local time of the daily reminder, today or tomorrow.
+// A reminder in a skipped daylight saving hour is not sent that day
+pub fn get_next_reminder(reminder: NaiveTime, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let today = now.date_naive().and_time(reminder);
+    let next = match today > now.naive_local() {
+        true => today,
+        false => today + Days::new(1),
+    };
+
+    Local.from_local_datetime(&next).earliest()
+}
+
+/// Days in a row meeting the daily goal.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Streaks {
+    pub current: usize,
+    pub longest: usize,
+}
+
+impl Streaks {
+    // today does not break the current streak before it is over
+    pub fn new(days: &BTreeMap<NaiveDate, DayFocus>, goal: DailyGoal, today: NaiveDate) -> Self {
+        let is_met = |date: NaiveDate| days.get(&date).is_some_and(|day| goal.is_met(day));
+
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for date in days.keys().copied().filter(|date| is_met(*date)) {
+            run = match previous.and_then(|previous| previous.succ_opt()) {
+                Some(next) if next == date => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            previous = Some(date);
+        }
+
+        let mut current = 0;
+        let mut date = match is_met(today) {
+            true => Some(today),
+            false => today.pred_opt(),
+        };
+        while let Some(day) = date.filter(|day| is_met(*day)) {
+            current += 1;
+            date = day.pred_opt();
+        }
+
+        Streaks { current, longest }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, Days, Duration};
+
+    use super::{get_next_reminder, DailyGoal, DayFocus, GoalProgress, Streaks};
+    use crate::sled_databbase::NotificationSled;
+
+    #[test]
+    fn test_parse_daily_goal() {
+        assert_eq!(DailyGoal::parse("4"), Ok(DailyGoal::Pomodoros(4)));
+        assert_eq!(DailyGoal::parse("90m"), Ok(DailyGoal::Minutes(90)));
+        assert_eq!(DailyGoal::parse("2h"), Ok(DailyGoal::Minutes(120)));
+        for goal in [DailyGoal::Pomodoros(8), DailyGoal::Minutes(150)] {
+            assert_eq!(DailyGoal::parse(&goal.to_arg()), Ok(goal));
+        }
+        assert!(DailyGoal::parse("0").is_err());
+        assert!(DailyGoal::parse("30s").is_err());
+        assert!(DailyGoal::parse("a lot").is_err());
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        let now = Local
+            .with_ymd_and_hms(2024, 3, 20, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let notifications = [3, 2, 1]
+            .iter()
+            .map(|hours_ago| {
+                NotificationSled::new(
+                    String::from("write"),
+                    25 * 60,
+                    5 * 60,
+                    now - Duration::hours(*hours_ago),
+                )
+            })
+            .collect::<Vec<NotificationSled>>();
+
+        let goal = DailyGoal::Pomodoros(3);
+        let progress = GoalProgress::today(goal, &notifications, now);
+        assert!(progress.is_reached());
+        assert_eq!(progress.to_string(), "3/3 pomodoros");
+
+        // the goal is celebrated when the last pomodoro reaches it, not after
+        let last = notifications[2].id;
+        assert_eq!(
+            GoalProgress::reached_with(goal, &notifications, last, now),
+            Some(progress)
+        );
+        let goal = DailyGoal::Pomodoros(2);
+        assert_eq!(
+            GoalProgress::reached_with(goal, &notifications, last, now),
+            None
+        );
+
+        let progress = GoalProgress::today(DailyGoal::Minutes(100), &notifications, now);
+        assert!(!progress.is_reached());
+        assert_eq!(progress.get_short(), "75/100m");
+    }
+
+    #[test]
+    fn test_next_reminder() {
+        let reminder = NaiveTime::from_hms_opt(21, 0, 0).unwrap();
+        let at = |day: u32, hour: u32| Local.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap();
+
+        assert_eq!(get_next_reminder(reminder, at(20, 12)), Some(at(20, 21)));
+        assert_eq!(get_next_reminder(reminder, at(20, 21)), Some(at(21, 21)));
+        assert_eq!(get_next_reminder(reminder, at(20, 22)), Some(at(21, 21)));
+    }
+
+    #[test]
+    fn test_streaks() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 20).unwrap();
+        let day = |days_ago: u64, completed: usize| {
+            let date = today - Days::new(days_ago);
+            let day = DayFocus {
+                date,
+                completed,
+                focus_minutes: completed as u64 * 25,
+            };
+            (date, day)
+        };
+        // a run of 4 days long ago, then 2 days until yesterday, today just started
+        let days = [
+            day(10, 4),
+            day(9, 5),
+            day(8, 4),
+            day(7, 4),
+            day(5, 1),
+            day(2, 4),
+            day(1, 6),
+            day(0, 1),
+        ]
+        .into_iter()
+        .collect();
+
+        let goal = DailyGoal::Pomodoros(4);
+        assert_eq!(
+            Streaks::new(&days, goal, today),
+            Streaks {
+                current: 2,
+                longest: 4
+            }
+        );
+
+        // every day with a pomodoro meets a goal of 25 minutes
+        let goal = DailyGoal::Minutes(25);
+        assert_eq!(
+            Streaks::new(&days, goal, today),
+            Streaks {
+                current: 3,
+                longest: 4
+            }
+        );
+
+        let goal = DailyGoal::Pomodoros(6);
+        assert_eq!(Streaks::new(&days, goal, today).current, 1);
+        assert_eq!(
+            Streaks::new(&days, goal, today + Days::new(2)),
+            Streaks {
+                current: 0,
+                longest: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use chrono::{prelude::*, Days};
+use colored::{ColoredString, Colorize};
+use serde::{Deserialize, Serialize};
+
+use crate::goal::{get_day_focus, DailyGoal, DayFocus, Streaks};
+use crate::sled_databbase::NotificationSled;
+
+pub const DEFAULT_HEATMAP_WEEKS: u16 = 26;
+
+const CELL: &str = "■";
+// focused days are split in levels by their share of the most focused day
+const LEVELS: usize = 4;
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+
+/// Focus minutes per day of the last weeks, with the streaks of days meeting the goal.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Heatmap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub goal: DailyGoal,
+    // every day from the monday of the first week until today
+    pub days: Vec<DayFocus>,
+    // streaks are counted on the whole history
+    pub streaks: Streaks,
+}
+
+impl Heatmap {
+    pub fn new(
+        notifications: &[NotificationSled],
+        weeks: u16,
+        goal: DailyGoal,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let today = now.with_timezone(&Local).date_naive();
+        let this_monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let from = this_monday - Days::new(7 * (weeks.max(1) as u64 - 1));
+
+        let day_focus = get_day_focus(notifications, now);
+        let days = from
+            .iter_days()
+            .take_while(|date| *date <= today)
+            .map(|date| {
+                day_focus
+                    .get(&date)
+                    .copied()
+                    .unwrap_or_else(|| DayFocus::new(date))
+            })
+            .collect();
+
+        Heatmap {
+            from,
+            to: today,
+            goal,
+            days,
+            streaks: Streaks::new(&day_focus, goal, today),
+        }
+    }
+
+    pub fn from_json(s: &str) -> Option<Self> {
+        serde_json::from_str(s).ok()
+    }
+
+    pub fn render(&self) -> String {
+        let max_focus = self
+            .days
+            .iter()
+            .map(|day| day.focus_minutes)
+            .max()
+            .unwrap_or_default();
+        let weeks = self.days.chunks(7).collect::<Vec<&[DayFocus]>>();
+
+        // a month is labeled above its first week, or the next one when there is no room
+        let mut months = String::from("    ");
+        let mut previous_month = None;
+        for (column, week) in weeks.iter().enumerate() {
+            let position = 4 + column * 2;
+            let month = week[0].date.month();
+            if previous_month != Some(month) && months.len() <= position {
+                months.push_str(&" ".repeat(position - months.len()));
+                months.push_str(&week[0].date.format("%b").to_string());
+                previous_month = Some(month);
+            }
+        }
+
+        let mut lines = vec![months];
+        for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+            let cells = weeks
+                .iter()
+                .map(|week| match week.get(weekday) {
+                    Some(day) => format!("{} ", get_cell(day, max_focus)),
+                    None => String::from("  "),
+                })
+                .collect::<String>();
+            lines.push(format!("{:<4}{}", label, cells).trim_end().to_string());
+        }
+
+        let legend = (0..=LEVELS)
+            .map(|level| get_level_cell(level).to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        lines.push(format!("    less {} more", legend));
+        lines.push(String::new());
+        let days = |count: usize| match count {
+            1 => String::from("1 day"),
+            count => format!("{} days", count),
+        };
+        lines.push(format!(
+            "current streak: {}, longest streak: {} (goal: {} a day)",
+            days(self.streaks.current),
+            days(self.streaks.longest),
+            self.goal
+        ));
+
+        lines.join("\n")
+    }
+}
+
+fn get_cell(day: &DayFocus, max_focus: u64) -> ColoredString {
+    let level = match day.completed {
+        0 => 0,
+        _ => ((day.focus_minutes as usize * LEVELS).div_ceil(max_focus.max(1) as usize))
+            .clamp(1, LEVELS),
+    };
+
+    get_level_cell(level)
+}
+
+// greens get brighter with the focus, plain ansi colors work on any terminal
+fn get_level_cell(level: usize) -> ColoredString {
+    match level {
+        0 => CELL.bright_black(),
+        1 => CELL.green().dimmed(),
+        2 => CELL.green(),
+        3 => CELL.bright_green(),
+        _ => CELL.bright_green().bold(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, Duration};
+
+    use super::Heatmap;
+    use crate::goal::DailyGoal;
+    use crate::sled_databbase::NotificationSled;
+
+    #[test]
+    fn test_heatmap() {
+        // a wednesday noon
+        let now = Local
+            .with_ymd_and_hms(2024, 3, 20, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let notifications = [1, 1, 2, 3, 30]
+            .iter()
+            .map(|days_ago| {
+                NotificationSled::new(
+                    String::from("write"),
+                    25 * 60,
+                    5 * 60,
+                    now - Duration::days(*days_ago),
+                )
+            })
+            .collect::<Vec<NotificationSled>>();
+
+        let heatmap = Heatmap::new(&notifications, 4, DailyGoal::Pomodoros(1), now);
+        assert_eq!(heatmap.from, NaiveDate::from_ymd_opt(2024, 2, 26).unwrap());
+        assert_eq!(heatmap.to, NaiveDate::from_ymd_opt(2024, 3, 20).unwrap());
+        assert_eq!(heatmap.days.len(), 24);
+        let yesterday = heatmap.days[22];
+        assert_eq!((yesterday.completed, yesterday.focus_minutes), (2, 50));
+
+        // today is not over, the streak runs until yesterday. The day 30 days ago is out of the map
+        assert_eq!(heatmap.streaks.current, 3);
+        assert_eq!(heatmap.streaks.longest, 3);
+        assert_eq!(
+            heatmap.days.iter().map(|day| day.completed).sum::<usize>(),
+            4
+        );
+
+        let rendered = heatmap.render();
+        assert!(rendered.starts_with("    Feb Mar"));
+        assert!(rendered.contains("current streak: 3 days, longest streak: 3 days"));
+        // 7 weekdays between the month labels and the legend
+        assert_eq!(rendered.lines().count(), 11);
+
+        let json = serde_json::to_string(&heatmap).unwrap();
+        assert_eq!(Heatmap::from_json(&json), Some(heatmap));
+    }
+}
@@ -0,0 +1,701 @@
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Local;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::command::util;
+use crate::error::ParseError;
+use crate::event::EventSender;
+use crate::goal::DailyGoal;
+use crate::ipc::{MessageRequest, MessageResponse};
+use crate::stats::StatsRange;
+use crate::{InputSource, UserInput};
+
+#[derive(Clone)]
+struct HttpState {
+    user_input_tx: Sender<UserInput>,
+    events: EventSender,
+    token: String,
+}
+
+// a number of minutes, or a duration like `"90s"`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationBody {
+    Minutes(u16),
+    Text(String),
+}
+
+impl DurationBody {
+    fn to_seconds(&self) -> Result<u32, ParseError> {
+        match self {
+            DurationBody::Minutes(minutes) => Ok(*minutes as u32 * 60),
+            DurationBody::Text(duration) => util::parse_duration(duration),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct CreateBody {
+    work: Option<DurationBody>,
+    r#break: Option<DurationBody>,
+    description: Option<String>,
+    preset: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    project: Option<String>,
+    task: Option<u64>,
+    // `"14:00"` or a rfc3339 time, `/create` only
+    at: Option<String>,
+    // a duration like `"20m"`, `/create` only
+    r#in: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct DeleteBody {
+    id: Option<String>,
+    all: Option<bool>,
+}
+
+// `/pause` and `/resume`, every running or paused pomodoro without an id
+#[derive(Deserialize, Default)]
+struct TimerBody {
+    id: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct InterruptBody {
+    external: Option<bool>,
+    note: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct ListQuery {
+    percentage: Option<bool>,
+    tag: Option<String>,
+    project: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct HistoryQuery {
+    tag: Option<String>,
+    project: Option<String>,
+}
+
+// `period` is day, week or month, otherwise `from` (and `to`) give the days
+#[derive(Deserialize, Default)]
+struct StatsQuery {
+    period: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct HeatmapQuery {
+    weeks: Option<u16>,
+    goal: Option<String>,
+}
+
+/// spawn_server serves the http api on the loopback interface only.
+/// Requests are turned into `MessageRequest` and handled like the ones from the uds client.
+pub fn spawn_server(
+    port: u16,
+    token: String,
+    user_input_tx: Sender<UserInput>,
+    events: EventSender,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("failed to bind http api to {}: {}", addr, e);
+                return;
+            }
+        };
+        info!("http api listening on http://{}", addr);
+
+        let state = HttpState {
+            user_input_tx,
+            events,
+            token,
+        };
+
+        if let Err(e) = axum::serve(listener, router(state)).await {
+            error!("http api stopped: {}", e);
+        }
+    })
+}
+
+fn router(state: HttpState) -> Router {
+    Router::new()
+        .route("/create", post(handle_create))
+        .route("/queue", post(handle_queue))
+        .route("/delete", post(handle_delete))
+        .route("/pause", post(handle_pause))
+        .route("/resume", post(handle_resume))
+        .route("/interrupt", post(handle_interrupt))
+        .route("/list", get(handle_list))
+        .route("/status", get(handle_status))
+        .route("/history", get(handle_history).delete(handle_clear_history))
+        .route("/stats", get(handle_stats))
+        .route("/heatmap", get(handle_heatmap))
+        .route("/events", get(handle_events))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+// the token is accepted as bearer token, or as `token` query for EventSource which can't set headers
+async fn authorize(
+    State(state): State<HttpState>,
+    Query(query): Query<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = bearer.or_else(|| query.get("token").map(|s| s.as_str()));
+
+    match token {
+        Some(token) if is_same_token(token, &state.token) => next.run(request).await,
+        _ => error_response(StatusCode::UNAUTHORIZED, "invalid or missing token"),
+    }
+}
+
+// compare in constant time to not leak the token length of the matching prefix
+fn is_same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+// get_body falls back to the default body only when the request has no body at all,
+// a malformed body or one without the json content type is rejected
+fn get_body<T: Default>(
+    headers: &HeaderMap,
+    body: Result<Json<T>, JsonRejection>,
+) -> Result<T, Response> {
+    match body {
+        Ok(Json(body)) => Ok(body),
+        Err(_) if is_empty_body(headers) => Ok(T::default()),
+        Err(rejection) => Err(error_response(
+            StatusCode::BAD_REQUEST,
+            &rejection.body_text(),
+        )),
+    }
+}
+
+// a http/1.1 request carries a body only with a content length or a transfer encoding
+fn is_empty_body(headers: &HeaderMap) -> bool {
+    match headers.get(header::CONTENT_LENGTH) {
+        Some(length) => length.as_bytes() == b"0",
+        None => !headers.contains_key(header::TRANSFER_ENCODING),
+    }
+}
+
+fn check_create_body(body: &CreateBody) -> Option<Response> {
+    let preset = body.preset.as_deref().map(util::parse_preset_name);
+    let labels = body
+        .tags
+        .iter()
+        .map(|tag| tag.as_str())
+        .chain(body.project.as_deref())
+        .map(util::parse_label);
+
+    preset
+        .into_iter()
+        .chain(labels)
+        .find_map(|result| result.err())
+        .map(|e| error_response(StatusCode::BAD_REQUEST, &e))
+}
+
+fn check_labels<'a>(labels: impl IntoIterator<Item = Option<&'a str>>) -> Option<Response> {
+    labels
+        .into_iter()
+        .flatten()
+        .find_map(|label| util::parse_label(label).err())
+        .map(|e| error_response(StatusCode::BAD_REQUEST, &e))
+}
+
+// get_durations returns the work and break durations in seconds
+fn get_durations(body: &CreateBody) -> Result<(Option<u32>, Option<u32>), ParseError> {
+    let work = body
+        .work
+        .as_ref()
+        .map(DurationBody::to_seconds)
+        .transpose()?;
+    let r#break = body
+        .r#break
+        .as_ref()
+        .map(DurationBody::to_seconds)
+        .transpose()?;
+
+    Ok((work, r#break))
+}
+
+fn get_start_at(body: &CreateBody) -> Result<Option<i64>, ParseError> {
+    let start_at = util::resolve_start_at(body.at.as_deref(), body.r#in.as_deref(), &Local::now())?;
+
+    Ok(start_at.map(|start_at| start_at.timestamp()))
+}
+
+async fn handle_create(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    body: Result<Json<CreateBody>, JsonRejection>,
+) -> Response {
+    let body = match get_body(&headers, body) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    if let Some(response) = check_create_body(&body) {
+        return response;
+    }
+    let (start_at, (work, r#break)) = match get_start_at(&body)
+        .and_then(|start_at| get_durations(&body).map(|durations| (start_at, durations)))
+    {
+        Ok(values) => values,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+
+    dispatch(
+        &state,
+        MessageRequest::Create {
+            work,
+            r#break,
+            desciption: body.description,
+            preset: body.preset,
+            tags: body.tags,
+            project: body.project,
+            task: body.task,
+            start_at,
+        },
+    )
+    .await
+}
+
+async fn handle_queue(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    body: Result<Json<CreateBody>, JsonRejection>,
+) -> Response {
+    let body = match get_body(&headers, body) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    if let Some(response) = check_create_body(&body) {
+        return response;
+    }
+    let (work, r#break) = match get_durations(&body) {
+        Ok(durations) => durations,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+
+    dispatch(
+        &state,
+        MessageRequest::Queue {
+            work,
+            r#break,
+            desciption: body.description,
+            preset: body.preset,
+            tags: body.tags,
+            project: body.project,
+            task: body.task,
+        },
+    )
+    .await
+}
+
+async fn handle_delete(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    body: Result<Json<DeleteBody>, JsonRejection>,
+) -> Response {
+    let body = match get_body(&headers, body) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    let request = match (body.id, body.all.unwrap_or(false)) {
+        (Some(id), false) => match util::parse_id_prefix(&id) {
+            Ok(id) => MessageRequest::Delete {
+                id: Some(id),
+                all: false,
+            },
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+        },
+        (None, true) => MessageRequest::Delete {
+            id: None,
+            all: true,
+        },
+        _ => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "either `id` or `all: true` is required",
+            )
+        }
+    };
+
+    dispatch(&state, request).await
+}
+
+async fn handle_pause(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    body: Result<Json<TimerBody>, JsonRejection>,
+) -> Response {
+    match parse_timer_id(&headers, body) {
+        Ok(id) => dispatch(&state, MessageRequest::Pause { id }).await,
+        Err(response) => response,
+    }
+}
+
+async fn handle_resume(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    body: Result<Json<TimerBody>, JsonRejection>,
+) -> Response {
+    match parse_timer_id(&headers, body) {
+        Ok(id) => dispatch(&state, MessageRequest::Resume { id }).await,
+        Err(response) => response,
+    }
+}
+
+fn parse_timer_id(
+    headers: &HeaderMap,
+    body: Result<Json<TimerBody>, JsonRejection>,
+) -> Result<Option<String>, Response> {
+    let body = get_body(headers, body)?;
+    body.id
+        .map(|id| util::parse_id_prefix(&id))
+        .transpose()
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, &e))
+}
+
+async fn handle_interrupt(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    body: Result<Json<InterruptBody>, JsonRejection>,
+) -> Response {
+    let body = match get_body(&headers, body) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    // checked like the note given on the command line
+    let note = match body.note.as_deref().map(util::check_note).transpose() {
+        Ok(note) => note,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+
+    dispatch(
+        &state,
+        MessageRequest::Interrupt {
+            external: body.external.unwrap_or(false),
+            note,
+        },
+    )
+    .await
+}
+
+async fn handle_list(State(state): State<HttpState>, Query(query): Query<ListQuery>) -> Response {
+    if let Some(response) = check_labels([query.tag.as_deref(), query.project.as_deref()]) {
+        return response;
+    }
+
+    dispatch(
+        &state,
+        MessageRequest::List {
+            show_percentage: query.percentage.unwrap_or(false),
+            tag: query.tag,
+            project: query.project,
+        },
+    )
+    .await
+}
+
+async fn handle_status(State(state): State<HttpState>) -> Response {
+    dispatch(&state, MessageRequest::Status).await
+}
+
+async fn handle_history(
+    State(state): State<HttpState>,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    if let Some(response) = check_labels([query.tag.as_deref(), query.project.as_deref()]) {
+        return response;
+    }
+
+    dispatch(
+        &state,
+        MessageRequest::History {
+            should_clear: false,
+            tag: query.tag,
+            project: query.project,
+        },
+    )
+    .await
+}
+
+// clearing the history is a mutation, so it is not reachable with a get
+async fn handle_clear_history(
+    State(state): State<HttpState>,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    if let Some(response) = check_labels([query.tag.as_deref(), query.project.as_deref()]) {
+        return response;
+    }
+
+    dispatch(
+        &state,
+        MessageRequest::History {
+            should_clear: true,
+            tag: query.tag,
+            project: query.project,
+        },
+    )
+    .await
+}
+
+async fn handle_stats(State(state): State<HttpState>, Query(query): Query<StatsQuery>) -> Response {
+    let range = match get_stats_range(&query) {
+        Ok(range) => range,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+
+    dispatch(
+        &state,
+        MessageRequest::Stats {
+            from: range.from.to_string(),
+            to: range.to.to_string(),
+        },
+    )
+    .await
+}
+
+fn get_stats_range(query: &StatsQuery) -> Result<StatsRange, ParseError> {
+    let parse_date = |date: &Option<String>| {
+        date.as_deref()
+            .map(util::parse_date)
+            .transpose()
+            .map_err(ParseError::new)
+    };
+
+    util::resolve_stats_range(
+        query.period.as_deref(),
+        parse_date(&query.from)?,
+        parse_date(&query.to)?,
+        Local::now().date_naive(),
+    )
+}
+
+async fn handle_heatmap(
+    State(state): State<HttpState>,
+    Query(query): Query<HeatmapQuery>,
+) -> Response {
+    // the goal is written back in its canonical form, the raw text never reaches the command line
+    let goal = match query.goal.as_deref().map(DailyGoal::parse).transpose() {
+        Ok(goal) => goal.map(|goal| goal.to_arg()),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+
+    dispatch(
+        &state,
+        MessageRequest::Heatmap {
+            weeks: query.weeks,
+            goal,
+        },
+    )
+    .await
+}
+
+async fn handle_events(
+    State(state): State<HttpState>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    // lagged subscribers just skip the dropped events
+    let stream = BroadcastStream::new(state.events.subscribe())
+        .filter_map(|event| event.ok())
+        .map(|event| Ok(SseEvent::default().data(event.to_json())));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn dispatch(state: &HttpState, request: MessageRequest) -> Response {
+    let (tx, rx) = oneshot::channel();
+    let user_input = UserInput {
+        input: request.into(),
+        source: InputSource::Http(tx),
+    };
+
+    if state.user_input_tx.send(user_input).await.is_err() {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "server is not running");
+    }
+
+    match rx.await {
+        Ok(Ok(response)) => Json(to_json(&response)).into_response(),
+        Ok(Err(message)) => error_response(StatusCode::BAD_REQUEST, &message),
+        Err(_) => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "request was dropped without response",
+        ),
+    }
+}
+
+fn to_json(response: &MessageResponse) -> Value {
+    let data = response
+        .get_data()
+        .and_then(|data| serde_json::from_str::<Value>(data).ok())
+        .unwrap_or(Value::Null);
+
+    json!({
+        "messages": response.get_body(),
+        "data": data,
+    })
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::extract::{FromRequest, Request};
+    use axum::http::header;
+    use axum::Json;
+    use serde_json::json;
+
+    use super::{
+        check_create_body, get_body, get_durations, get_stats_range, is_same_token, to_json,
+        CreateBody, StatsQuery,
+    };
+    use crate::ipc::MessageResponse;
+
+    #[test]
+    fn test_is_same_token() {
+        assert!(is_same_token("secret", "secret"));
+        assert!(!is_same_token("secret", "secreT"));
+        assert!(!is_same_token("secret", "secret2"));
+        assert!(!is_same_token("", "secret"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let response = MessageResponse::with_data(
+            vec![String::from("List succeed")],
+            Some(String::from("[]")),
+        );
+        assert_eq!(
+            to_json(&response),
+            json!({ "messages": ["List succeed"], "data": [] })
+        );
+
+        let response = MessageResponse::new(vec![String::from("Notification Test called")]);
+        assert_eq!(
+            to_json(&response),
+            json!({ "messages": ["Notification Test called"], "data": null })
+        );
+    }
+
+    #[test]
+    fn test_get_durations() {
+        let body: CreateBody =
+            serde_json::from_value(json!({ "work": 25, "break": "30s" })).unwrap();
+        assert_eq!(get_durations(&body).unwrap(), (Some(25 * 60), Some(30)));
+
+        let body: CreateBody = serde_json::from_value(json!({ "work": "1h" })).unwrap();
+        assert_eq!(get_durations(&body).unwrap(), (Some(3600), None));
+
+        let body: CreateBody = serde_json::from_value(json!({ "work": "soon" })).unwrap();
+        assert!(get_durations(&body).is_err());
+    }
+
+    #[test]
+    fn test_check_create_body() {
+        let body: CreateBody =
+            serde_json::from_value(json!({ "tags": ["client"], "project": "billing" })).unwrap();
+        assert!(check_create_body(&body).is_none());
+
+        let body: CreateBody = serde_json::from_value(json!({ "tags": ["a b"] })).unwrap();
+        assert!(check_create_body(&body).is_some());
+    }
+
+    #[test]
+    fn test_get_stats_range() {
+        let query: StatsQuery =
+            serde_json::from_value(json!({ "from": "2024-03-01", "to": "2024-03-10" })).unwrap();
+        let range = get_stats_range(&query).unwrap();
+        assert_eq!(range.from.to_string(), "2024-03-01");
+        assert_eq!(range.to.to_string(), "2024-03-10");
+
+        let query: StatsQuery = serde_json::from_value(json!({ "period": "week" })).unwrap();
+        assert!(get_stats_range(&query).is_ok());
+
+        for query in [
+            json!({ "period": "year" }),
+            json!({ "period": "week", "from": "2024-03-01" }),
+            json!({ "from": "2024-03-01 --to" }),
+            json!({ "to": "2024-03-01" }),
+        ] {
+            let query: StatsQuery = serde_json::from_value(query).unwrap();
+            assert!(get_stats_range(&query).is_err());
+        }
+    }
+
+    async fn parse_create_body(request: Request) -> Result<CreateBody, u16> {
+        let headers = request.headers().clone();
+        let body = Json::<CreateBody>::from_request(request, &()).await;
+        get_body(&headers, body).map_err(|response| response.status().as_u16())
+    }
+
+    #[tokio::test]
+    async fn test_get_body() {
+        let json_request = |body: &'static str| {
+            Request::post("/create")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let body = parse_create_body(json_request(r#"{"work": 30}"#))
+            .await
+            .unwrap();
+        assert_eq!(get_durations(&body).unwrap(), (Some(30 * 60), None));
+
+        // no body at all is the default request
+        let request = Request::post("/create").body(Body::empty()).unwrap();
+        let body = parse_create_body(request).await.unwrap();
+        assert!(body.work.is_none());
+
+        for request in [
+            json_request(r#"{"work": 30"#),
+            json_request(r#"{"tags": "client"}"#),
+            Request::post("/create")
+                .header(header::CONTENT_LENGTH, "12")
+                .body(Body::from(r#"{"work": 30}"#))
+                .unwrap(),
+        ] {
+            assert_eq!(parse_create_body(request).await.err(), Some(400));
+        }
+    }
+}
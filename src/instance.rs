@@ -0,0 +1,226 @@
+use clap::ArgMatches;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::command::util;
+use crate::error::ParseError;
+
+const DEFAULT_PREFIX: &str = "rust-cli-pomodoro";
+const SOCKET_ENV: &str = "POMODORO_SOCKET";
+const RUNTIME_DIR_ENV: &str = "XDG_RUNTIME_DIR";
+const DATA_HOME_ENV: &str = "XDG_DATA_HOME";
+const DATA_DIR_NAME: &str = "pomodoro";
+const DATABASE_NAME: &str = "sled_databbase";
+
+static INSTANCE: OnceLock<Instance> = OnceLock::new();
+
+/// Instance decides where a server keeps its sockets, data, pid and log files,
+/// so separate timers (`--instance work`, `--instance personal`) don't share them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    name: Option<String>,
+    socket: Option<PathBuf>,
+}
+
+impl Instance {
+    pub fn new(name: Option<String>, socket: Option<PathBuf>) -> Result<Self, ParseError> {
+        if let Some(name) = &name {
+            let is_valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !is_valid {
+                return Err(ParseError::new(format!(
+                    "instance name ({}) may only contain letters, digits, '-' and '_'",
+                    name
+                )));
+            }
+        }
+
+        Ok(Instance { name, socket })
+    }
+
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, ParseError> {
+        let matches = util::get_leaf_matches(matches);
+        let name = matches.get_one::<String>("instance").cloned();
+        let socket = matches
+            .get_one::<String>("socket")
+            .cloned()
+            .or_else(|| env::var(SOCKET_ENV).ok().filter(|s| !s.is_empty()))
+            // client and server may run in different directories
+            .map(|socket| match env::current_dir() {
+                Ok(dir) => dir.join(socket),
+                Err(_) => PathBuf::from(socket),
+            });
+
+        Instance::new(name, socket)
+    }
+
+    // file name prefix of the sockets, pid and log files
+    pub fn get_prefix(&self) -> String {
+        match (&self.socket, &self.name) {
+            (Some(socket), _) => socket
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(DEFAULT_PREFIX)
+                .to_string(),
+            (None, Some(name)) => format!("{}-{}", DEFAULT_PREFIX, name),
+            (None, None) => DEFAULT_PREFIX.to_string(),
+        }
+    }
+
+    // the directory of the sockets, `$XDG_RUNTIME_DIR/rust-cli-pomodoro` or `$TMPDIR/$USER`
+    // unless `--socket` is given
+    pub fn get_socket_dir(&self) -> PathBuf {
+        match self.socket.as_ref().and_then(|socket| socket.parent()) {
+            Some(parent) if parent != Path::new("") => parent.to_path_buf(),
+            Some(_) => PathBuf::from("."),
+            None => match env::var_os(RUNTIME_DIR_ENV).filter(|dir| !dir.is_empty()) {
+                Some(runtime_dir) => PathBuf::from(runtime_dir).join(DEFAULT_PREFIX),
+                None => {
+                    let username = env::var("USER").unwrap_or("default".to_string());
+                    env::temp_dir().join(username)
+                }
+            },
+        }
+    }
+
+    // the socket directory was chosen by the user, so we don't change its permissions
+    pub fn has_custom_socket(&self) -> bool {
+        self.socket.is_some()
+    }
+
+    pub fn get_server_socket_name(&self) -> String {
+        match self.socket.as_ref().and_then(|socket| socket.file_name()) {
+            Some(file_name) => file_name.to_string_lossy().to_string(),
+            None => format!("{}-server.sock", self.get_prefix()),
+        }
+    }
+
+    // named instances keep their database in a sub directory of the default one
+    pub fn get_database_path(&self) -> PathBuf {
+        let data_dir = get_data_dir(env::var_os(DATA_HOME_ENV), env::var_os("HOME"));
+        match &self.name {
+            Some(name) => data_dir.join(name).join(DATABASE_NAME),
+            None => data_dir.join(DATABASE_NAME),
+        }
+    }
+
+    // flags that select this instance, passed on to a spawned daemon
+    pub fn get_flags(&self) -> Vec<(&'static str, String)> {
+        let mut flags = Vec::new();
+        if let Some(name) = &self.name {
+            flags.push(("instance", name.clone()));
+        }
+        if let Some(socket) = &self.socket {
+            flags.push(("socket", socket.display().to_string()));
+        }
+
+        flags
+    }
+}
+
+// get_data_dir returns `$XDG_DATA_HOME/pomodoro`, or `$HOME/.local/share/pomodoro` when it is
+// not set. The spec ignores relative paths
+fn get_data_dir(data_home: Option<OsString>, home: Option<OsString>) -> PathBuf {
+    let is_usable = |dir: &OsString| PathBuf::from(dir).is_absolute();
+    match (data_home.filter(is_usable), home.filter(is_usable)) {
+        (Some(data_home), _) => PathBuf::from(data_home).join(DATA_DIR_NAME),
+        (None, Some(home)) => PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join(DATA_DIR_NAME),
+        (None, None) => env::temp_dir().join(DATA_DIR_NAME),
+    }
+}
+
+pub fn initialize(instance: Instance) {
+    debug!("instance: {:?}", instance);
+    if INSTANCE.set(instance).is_err() {
+        debug!("instance is already initialized");
+    }
+}
+
+// get returns the instance of this process, the default one until initialize is called
+pub fn get() -> &'static Instance {
+    INSTANCE.get_or_init(|| Instance {
+        name: None,
+        socket: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    use super::{get_data_dir, Instance};
+
+    #[test]
+    fn test_default_instance() {
+        let instance = Instance::new(None, None).unwrap();
+        assert_eq!(instance.get_prefix(), "rust-cli-pomodoro");
+        assert_eq!(
+            instance.get_server_socket_name(),
+            "rust-cli-pomodoro-server.sock"
+        );
+        assert!(instance
+            .get_database_path()
+            .ends_with("pomodoro/sled_databbase"));
+        assert!(instance.get_flags().is_empty());
+    }
+
+    #[test]
+    fn test_named_instance() {
+        let instance = Instance::new(Some(String::from("work")), None).unwrap();
+        assert_eq!(instance.get_prefix(), "rust-cli-pomodoro-work");
+        assert_eq!(
+            instance.get_server_socket_name(),
+            "rust-cli-pomodoro-work-server.sock"
+        );
+        assert!(instance
+            .get_database_path()
+            .ends_with("pomodoro/work/sled_databbase"));
+
+        assert!(Instance::new(Some(String::from("../etc")), None).is_err());
+        assert!(Instance::new(Some(String::new()), None).is_err());
+    }
+
+    #[test]
+    fn test_data_dir() {
+        let home = Some(OsString::from("/home/user"));
+        assert_eq!(
+            get_data_dir(Some(OsString::from("/data")), home.clone()),
+            PathBuf::from("/data/pomodoro")
+        );
+        assert_eq!(
+            get_data_dir(None, home.clone()),
+            PathBuf::from("/home/user/.local/share/pomodoro")
+        );
+        // empty and relative values are ignored
+        assert_eq!(
+            get_data_dir(Some(OsString::new()), home.clone()),
+            PathBuf::from("/home/user/.local/share/pomodoro")
+        );
+        assert_eq!(
+            get_data_dir(Some(OsString::from("data")), home),
+            PathBuf::from("/home/user/.local/share/pomodoro")
+        );
+        assert!(get_data_dir(None, None).ends_with("pomodoro"));
+    }
+
+    #[test]
+    fn test_socket_instance() {
+        let socket = PathBuf::from("/run/user/1000/pomodoro.sock");
+        let instance = Instance::new(None, Some(socket.clone())).unwrap();
+        assert_eq!(instance.get_prefix(), "pomodoro");
+        assert_eq!(instance.get_socket_dir(), PathBuf::from("/run/user/1000"));
+        assert_eq!(instance.get_server_socket_name(), "pomodoro.sock");
+        assert_eq!(
+            instance.get_flags(),
+            vec![("socket", String::from("/run/user/1000/pomodoro.sock"))]
+        );
+    }
+}
@@ -2,27 +2,39 @@ use bincode::error::DecodeError;
 use bincode::error::EncodeError;
 use bincode::Decode;
 use bincode::Encode;
-use std::env;
-use std::fs;
+use chrono::DateTime;
+use std::fs::{self, DirBuilder};
 use std::io::{Error, ErrorKind};
-use std::path::PathBuf;
+use std::ops::Deref;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process;
 use std::time::Duration;
 use tokio::net::UnixDatagram;
 use tokio::time::timeout;
 
 use crate::command::action::ActionType;
-use crate::InputSource;
-use crate::UserInput;
+use crate::instance;
 
-const SOCKET_SERVER_ADDR: &str = "rust-cli-pomodoro-server.sock";
-const SOCKET_CLIENT_ADDR: &str = "rust-cli-pomodoro-client.sock";
+const SOCKET_CLIENT_ADDR_INFIX: &str = "-client-";
+const SOCKET_SUBSCRIBER_ADDR_INFIX: &str = "-subscriber-";
 
-const CHUNK: usize = 2048;
+// messages are sent in datagrams of at most this size, followed by an empty one.
+// It is also the size of the receive buffers
+pub const CHUNK: usize = 2048;
+// a length prefix larger than this is rejected instead of allocated, it does not change the encoding
+const DECODE_LIMIT: usize = 16 * 1024 * 1024;
+
+fn get_decode_config() -> impl bincode::config::Config {
+    bincode::config::standard().with_limit::<DECODE_LIMIT>()
+}
 
 #[derive(Debug)]
 pub enum UdsType {
     Server,
-    Client,
+    // each client binds its own address, keyed by process id, so concurrent clients get their own responses
+    Client(u32),
+    Subscriber(u32),
 }
 
 #[derive(bincode::Encode, bincode::Decode, PartialEq, Debug, Eq)]
@@ -53,34 +65,103 @@ pub trait Bincodec {
         <Self as Bincodec>::Message: Decode,
     {
         let (message, _): (Self::Message, usize) =
-            bincode::decode_from_slice(byte, bincode::config::standard())?;
+            bincode::decode_from_slice(byte, get_decode_config())?;
 
         Ok(message)
     }
 }
 
-#[derive(bincode::Encode, bincode::Decode, PartialEq, Debug, Eq)]
+#[derive(bincode::Encode, bincode::Decode, PartialEq, Debug, Eq, Clone)]
 pub enum MessageRequest {
+    // durations in seconds
     Create {
-        work: Option<u16>,
-        r#break: Option<u16>,
+        work: Option<u32>,
+        r#break: Option<u32>,
         desciption: Option<String>,
+        preset: Option<String>,
+        tags: Vec<String>,
+        project: Option<String>,
+        task: Option<u64>,
+        // unix timestamp, resolved by the client from `--at` or `--in`
+        start_at: Option<i64>,
     },
     Queue {
-        work: Option<u16>,
-        r#break: Option<u16>,
+        work: Option<u32>,
+        r#break: Option<u32>,
         desciption: Option<String>,
+        preset: Option<String>,
+        tags: Vec<String>,
+        project: Option<String>,
+        task: Option<u64>,
     },
+    // an id prefix of an active notification, or all of them
     Delete {
-        id: u16,
+        id: Option<String>,
         all: bool,
     },
     List {
         show_percentage: bool,
+        tag: Option<String>,
+        project: Option<String>,
     },
     Test,
     History {
         should_clear: bool,
+        tag: Option<String>,
+        project: Option<String>,
+    },
+    // the server always answers with json, the client renders the requested format
+    Status,
+    Shutdown,
+    ReloadConfig,
+    Task(TaskRequest),
+    Interrupt {
+        external: bool,
+        note: Option<String>,
+    },
+    // without an id, every running (or paused) pomodoro is paused (or resumed)
+    Pause {
+        id: Option<String>,
+    },
+    Resume {
+        id: Option<String>,
+    },
+    // `id` is a notification uuid or its first characters
+    Note {
+        id: String,
+        note: String,
+    },
+    Rate {
+        id: String,
+        rating: u8,
+    },
+    // days as `YYYY-MM-DD`, resolved by the client from `--day`, `--week`, ...
+    Stats {
+        from: String,
+        to: String,
+    },
+    // the goal as written on the command line, e.g. `4` or `90m`
+    Heatmap {
+        weeks: Option<u16>,
+        goal: Option<String>,
+    },
+}
+
+#[derive(bincode::Encode, bincode::Decode, PartialEq, Debug, Eq, Clone)]
+pub enum TaskRequest {
+    Add {
+        title: String,
+        estimate: Option<u16>,
+    },
+    List {
+        all: bool,
+    },
+    Done {
+        id: u64,
+    },
+    Estimate {
+        id: u64,
+        estimate: u16,
     },
 }
 
@@ -88,92 +169,270 @@ impl Bincodec for MessageRequest {
     type Message = Self;
 }
 
-impl From<MessageRequest> for UserInput {
+fn push_option(args: &mut Vec<String>, flag: &str, value: impl ToString) {
+    args.push(flag.to_string());
+    args.push(value.to_string());
+}
+
+// push_id adds the optional positional id after `--`, so it is never read as a flag
+fn push_id(args: &mut Vec<String>, id: Option<String>) {
+    if let Some(id) = id {
+        args.push(String::from("--"));
+        args.push(id);
+    }
+}
+
+fn push_labels(args: &mut Vec<String>, tags: &[String], project: Option<&str>) {
+    for tag in tags {
+        push_option(args, "-t", tag)
+    }
+
+    if let Some(val) = project {
+        push_option(args, "--project", val)
+    }
+}
+
+fn push_label_filter(args: &mut Vec<String>, tag: Option<&str>, project: Option<&str>) {
+    if let Some(val) = tag {
+        push_option(args, "-t", val)
+    }
+
+    if let Some(val) = project {
+        push_option(args, "--project", val)
+    }
+}
+
+fn push_create_args(
+    args: &mut Vec<String>,
+    work: Option<u32>,
+    r#break: Option<u32>,
+    description: Option<String>,
+    preset: Option<String>,
+) {
+    if let Some(val) = work {
+        push_option(args, "-w", format!("{}s", val))
+    }
+
+    if let Some(val) = r#break {
+        push_option(args, "-b", format!("{}s", val))
+    }
+
+    // one argument, so the description keeps its spaces and may start with `-`
+    if let Some(val) = description {
+        args.push(format!("--description={}", val))
+    }
+
+    if let Some(val) = preset {
+        push_option(args, "-p", val)
+    }
+}
+
+// the arguments the server parses for a request, one argument per value so nothing is split again
+impl From<MessageRequest> for Vec<String> {
     fn from(request: MessageRequest) -> Self {
-        let input = match request {
+        let args = match request {
             MessageRequest::Create {
                 work,
                 r#break,
-                //TODO: implement description
-                desciption: _,
+                desciption,
+                preset,
+                tags,
+                project,
+                task,
+                start_at,
             } => {
-                let mut data = format!("{} ", String::from(ActionType::Create));
+                let mut args = vec![String::from(ActionType::Create)];
+                push_create_args(&mut args, work, r#break, desciption, preset);
+                push_labels(&mut args, &tags, project.as_deref());
 
-                if let Some(val) = work {
-                    data.push_str(&format!("-w {} ", val))
+                if let Some(val) = task {
+                    push_option(&mut args, "--task", val)
                 }
 
-                if let Some(val) = r#break {
-                    data.push_str(&format!("-b {}", val))
+                if let Some(val) = start_at.and_then(|val| DateTime::from_timestamp(val, 0)) {
+                    push_option(&mut args, "--at", val.to_rfc3339())
                 }
 
-                data
+                args
             }
             MessageRequest::Queue {
                 work,
                 r#break,
-                //TODO: implement description
-                desciption: _,
+                desciption,
+                preset,
+                tags,
+                project,
+                task,
             } => {
-                let mut data = format!("{} ", String::from(ActionType::Queue));
-
-                if let Some(val) = work {
-                    data.push_str(&format!("-w {} ", val))
-                }
+                let mut args = vec![String::from(ActionType::Queue)];
+                push_create_args(&mut args, work, r#break, desciption, preset);
+                push_labels(&mut args, &tags, project.as_deref());
 
-                if let Some(val) = r#break {
-                    data.push_str(&format!("-b {}", val))
+                if let Some(val) = task {
+                    push_option(&mut args, "--task", val)
                 }
 
-                data
+                args
             }
             MessageRequest::Delete { id, all } => {
-                if all {
-                    format!("{} -a", String::from(ActionType::Delete))
-                } else {
-                    format!("{} -i {}", String::from(ActionType::Delete), id)
+                let mut args = vec![String::from(ActionType::Delete)];
+                match (all, id) {
+                    (true, _) | (false, None) => args.push(String::from("-a")),
+                    (false, Some(id)) => push_option(&mut args, "-i", id),
                 }
+
+                args
             }
-            MessageRequest::List { show_percentage } => {
+            MessageRequest::List {
+                show_percentage,
+                tag,
+                project,
+            } => {
+                let mut args = vec![String::from(ActionType::List)];
                 if show_percentage {
-                    format!("{} -p", String::from(ActionType::List))
-                } else {
-                    String::from(ActionType::List)
+                    args.push(String::from("-p"));
                 }
+                push_label_filter(&mut args, tag.as_deref(), project.as_deref());
+
+                args
             }
-            MessageRequest::Test => String::from(ActionType::Test),
-            MessageRequest::History { should_clear } => {
+            MessageRequest::Test => vec![String::from(ActionType::Test)],
+            MessageRequest::History {
+                should_clear,
+                tag,
+                project,
+            } => {
+                let mut args = vec![String::from(ActionType::History)];
                 if should_clear {
-                    format!("{} --clear", String::from(ActionType::History))
-                } else {
-                    String::from(ActionType::History)
+                    args.push(String::from("--clear"));
                 }
+                push_label_filter(&mut args, tag.as_deref(), project.as_deref());
+
+                args
+            }
+            MessageRequest::Status => {
+                let mut args = vec![String::from(ActionType::Status)];
+                push_option(&mut args, "--format", "json");
+
+                args
+            }
+            MessageRequest::Shutdown => vec![String::from(ActionType::Shutdown)],
+            MessageRequest::ReloadConfig => vec![String::from(ActionType::ReloadConfig)],
+            MessageRequest::Interrupt { external, note } => {
+                let mut args = vec![String::from(ActionType::Interrupt)];
+                if external {
+                    args.push(String::from("--external"));
+                }
+                // the note goes last after `--`, so a leading dash is not read as a flag
+                if let Some(note) = note {
+                    args.extend([String::from("--"), note]);
+                }
+
+                args
+            }
+            MessageRequest::Pause { id } => {
+                let mut args = vec![String::from(ActionType::Pause)];
+                push_id(&mut args, id);
+
+                args
+            }
+            MessageRequest::Resume { id } => {
+                let mut args = vec![String::from(ActionType::Resume)];
+                push_id(&mut args, id);
+
+                args
+            }
+            MessageRequest::Note { id, note } => {
+                vec![String::from(ActionType::Note), String::from("--"), id, note]
+            }
+            MessageRequest::Rate { id, rating } => vec![
+                String::from(ActionType::Rate),
+                String::from("--"),
+                id,
+                rating.to_string(),
+            ],
+            MessageRequest::Stats { from, to } => {
+                let mut args = vec![String::from(ActionType::Stats)];
+                push_option(&mut args, "--from", from);
+                push_option(&mut args, "--to", to);
+
+                args
+            }
+            MessageRequest::Heatmap { weeks, goal } => {
+                let mut args = vec![String::from(ActionType::Heatmap)];
+                if let Some(val) = weeks {
+                    push_option(&mut args, "--weeks", val)
+                }
+
+                if let Some(val) = goal {
+                    push_option(&mut args, "--goal", val)
+                }
+
+                args
+            }
+            MessageRequest::Task(request) => {
+                let mut args = vec![String::from(ActionType::Task)];
+                match request {
+                    // the title goes last after `--`, it takes all remaining words
+                    TaskRequest::Add { title, estimate } => {
+                        args.push(String::from("add"));
+                        if let Some(val) = estimate {
+                            push_option(&mut args, "-e", val)
+                        }
+                        args.extend([String::from("--"), title]);
+                    }
+                    TaskRequest::List { all } => {
+                        args.push(String::from("ls"));
+                        if all {
+                            args.push(String::from("--all"));
+                        }
+                    }
+                    TaskRequest::Done { id } => {
+                        args.extend([String::from("done"), id.to_string()]);
+                    }
+                    TaskRequest::Estimate { id, estimate } => {
+                        args.extend([
+                            String::from("estimate"),
+                            id.to_string(),
+                            estimate.to_string(),
+                        ]);
+                    }
+                }
+
+                args
             }
         };
 
-        debug!("input: {:?}", input);
+        debug!("args: {:?}", args);
 
-        UserInput {
-            input,
-            source: InputSource::UnixDomainSocket,
-        }
+        args
     }
 }
 
 #[derive(bincode::Encode, bincode::Decode, PartialEq, Debug, Eq)]
 pub struct MessageResponse {
     body: Vec<String>,
+    // structured result serialized as json, present for list, history, create and queue
+    data: Option<String>,
 }
 
 impl MessageResponse {
     pub fn new(body: Vec<String>) -> Self {
-        MessageResponse { body }
+        MessageResponse { body, data: None }
+    }
+
+    pub fn with_data(body: Vec<String>, data: Option<String>) -> Self {
+        MessageResponse { body, data }
     }
 
     pub fn get_body(&self) -> &Vec<String> {
         &self.body
     }
 
+    pub fn get_data(&self) -> Option<&str> {
+        self.data.as_deref()
+    }
+
     pub fn print(self) {
         self.get_body().iter().for_each(|m| println!("{}", m));
     }
@@ -188,12 +447,14 @@ pub mod internal {
     use bincode::error::{DecodeError, EncodeError};
     use tokio::net::UnixDatagram;
 
-    use crate::command::handler::uds_client::BUFFER_LENGTH;
+    use super::CHUNK;
 
     #[derive(bincode::Encode, bincode::Decode, PartialEq, Debug, Eq)]
     pub enum Message {
         Ping,
         Pong,
+        // register the sender address to receive server events
+        Subscribe,
     }
 
     impl Message {
@@ -205,20 +466,20 @@ pub mod internal {
 
         pub fn decode(byte: &[u8]) -> Result<Self, DecodeError> {
             let (msg, _): (Message, usize) =
-                bincode::decode_from_slice(byte, bincode::config::standard())?;
+                bincode::decode_from_slice(byte, super::get_decode_config())?;
 
             Ok(msg)
         }
     }
 
     pub async fn decode_from_socket(
-        socket: UnixDatagram,
+        socket: &UnixDatagram,
     ) -> Result<Message, Box<dyn std::error::Error>> {
         let mut vec = Vec::new();
         let mut total_size = 0;
+        let mut buf = [0u8; CHUNK];
 
         loop {
-            let mut buf = vec![0u8; BUFFER_LENGTH];
             let (size, _) = socket.recv_from(&mut buf).await?;
 
             let dgram = &buf[..size];
@@ -291,22 +552,69 @@ pub async fn create_server_uds() -> Result<Option<UnixDatagram>, std::io::Error>
             return Err(e);
         }
     };
+    enable_peer_credentials(&socket)?;
 
     debug!("create_server_uds called");
     Ok(Some(socket))
 }
 
-pub async fn create_client_uds() -> Result<UnixDatagram, std::io::Error> {
-    let server_addr = get_uds_address(UdsType::Server);
-    let client_addr = create_uds_address(UdsType::Client, true)?;
+/// ClientUds is a socket connected to the server, its file is removed when it is dropped
+#[derive(Debug)]
+pub struct ClientUds {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl ClientUds {
+    fn bind(r#type: UdsType) -> std::io::Result<Self> {
+        let server_addr = get_uds_address(UdsType::Server);
+        let path = create_uds_address(r#type, true)?;
+        // don't send commands to a server run by someone else
+        verify_socket_owner(&server_addr)?;
+
+        let socket = UnixDatagram::bind(&path)?;
+        let client = ClientUds { socket, path };
+        client.socket.connect(server_addr)?;
+
+        Ok(client)
+    }
+}
+
+impl Deref for ClientUds {
+    type Target = UnixDatagram;
+
+    fn deref(&self) -> &Self::Target {
+        &self.socket
+    }
+}
+
+impl Drop for ClientUds {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            debug!("failed to remove client socket {:?}: {}", self.path, e);
+        }
+    }
+}
 
-    let socket = UnixDatagram::bind(client_addr)?;
-    socket.connect(server_addr)?;
+pub async fn create_client_uds() -> Result<ClientUds, std::io::Error> {
+    let socket = ClientUds::bind(UdsType::Client(process::id()))?;
 
     debug!("create_client_uds called");
     Ok(socket)
 }
 
+pub async fn create_subscriber_uds() -> Result<ClientUds, std::io::Error> {
+    let socket = ClientUds::bind(UdsType::Subscriber(process::id()))?;
+
+    debug!("create_subscriber_uds called");
+    Ok(socket)
+}
+
+// is_server_running answers whether a server replies to Ping
+pub async fn is_server_running() -> bool {
+    detect_address_in_use().await.unwrap_or(false)
+}
+
 async fn detect_address_in_use() -> Result<bool, std::io::Error> {
     debug!("detect_address_in_use called");
     let socket = create_client_uds().await?;
@@ -317,7 +625,7 @@ async fn detect_address_in_use() -> Result<bool, std::io::Error> {
         socket.send(
             UdsMessage::Internal(internal::Message::Ping)
                 .encode()
-                .unwrap()
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
                 .as_slice(),
         ),
     )
@@ -328,7 +636,7 @@ async fn detect_address_in_use() -> Result<bool, std::io::Error> {
 
     let timeout_result = timeout(
         Duration::from_millis(500),
-        internal::decode_from_socket(socket),
+        internal::decode_from_socket(&socket),
     )
     .await;
     match timeout_result {
@@ -349,12 +657,25 @@ async fn detect_address_in_use() -> Result<bool, std::io::Error> {
     Ok(false)
 }
 
+// prepare_instance_dir prepares the socket directory of the instance, which also holds
+// its default pid and log files, and returns it
+pub fn prepare_instance_dir() -> std::io::Result<PathBuf> {
+    let dir = instance::get().get_socket_dir();
+    prepare_socket_dir(&dir, !instance::get().has_custom_socket())?;
+
+    Ok(dir)
+}
+
 fn create_uds_address(r#type: UdsType, should_remove: bool) -> std::io::Result<PathBuf> {
     debug!("create_uds_address: {:?} - {}", &r#type, &should_remove);
 
     let path = get_uds_address(r#type);
+    prepare_instance_dir()?;
 
     if path.exists() {
+        // a socket of another user in our place means someone is trying to hijack it
+        verify_socket_owner(&path)?;
+
         if should_remove {
             debug!("path {:?} exists, remove it before binding", &path);
             fs::remove_file(&path)?;
@@ -382,36 +703,278 @@ fn create_uds_address(r#type: UdsType, should_remove: bool) -> std::io::Result<P
     Ok(path)
 }
 
+// get_uds_address returns the socket path of the current instance, see `instance::Instance`
 pub fn get_uds_address(r#type: UdsType) -> PathBuf {
+    let instance = instance::get();
     let socket_addr = match r#type {
-        UdsType::Server => SOCKET_SERVER_ADDR,
-        UdsType::Client => SOCKET_CLIENT_ADDR,
+        UdsType::Server => instance.get_server_socket_name(),
+        UdsType::Client(pid) => format!(
+            "{}{}{}.sock",
+            instance.get_prefix(),
+            SOCKET_CLIENT_ADDR_INFIX,
+            pid
+        ),
+        UdsType::Subscriber(pid) => format!(
+            "{}{}{}.sock",
+            instance.get_prefix(),
+            SOCKET_SUBSCRIBER_ADDR_INFIX,
+            pid
+        ),
     };
 
-    let mut p = env::temp_dir();
-    let username = env::var("USER").unwrap_or("default".to_string());
-    p.push(username); // Use the username to create a sub-directory
-    std::fs::create_dir_all(&p).expect("Failed to create directory");
+    let mut p = instance.get_socket_dir();
     p.push(socket_addr);
 
     debug!("get_uds_address, path: {:?}", p);
     p
 }
 
-pub async fn send_to(socket: &UnixDatagram, target: PathBuf, buf: &[u8]) {
+fn get_current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and always succeeds
+    unsafe { libc::getuid() }
+}
+
+// prepare_socket_dir makes sure no other user can create or replace our sockets.
+// The default directory is created (or fixed) with 0700, a custom one is only checked
+fn prepare_socket_dir(dir: &Path, is_default: bool) -> std::io::Result<()> {
+    if !dir.exists() {
+        DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        return Ok(());
+    }
+
+    let metadata = fs::metadata(dir)?;
+    if !metadata.is_dir() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("socket directory {:?} is not a directory", dir),
+        ));
+    }
+
+    let mode = metadata.permissions().mode();
+    let is_shared_with_sticky_bit = metadata.uid() == 0 && mode & 0o1000 != 0;
+    if metadata.uid() != get_current_uid() && !is_shared_with_sticky_bit {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "socket directory {:?} is owned by another user (uid: {}), refusing to use it",
+                dir,
+                metadata.uid()
+            ),
+        ));
+    }
+
+    if metadata.uid() == get_current_uid() && mode & 0o077 != 0 {
+        if is_default {
+            debug!("restrict permissions of socket directory {:?} to 0700", dir);
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        } else if mode & 0o022 != 0 {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "socket directory {:?} is writable by other users, run `chmod 700` on it",
+                    dir
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_socket_owner(path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.uid() != get_current_uid() => Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "socket {:?} is owned by another user (uid: {}), refusing to use it",
+                path,
+                metadata.uid()
+            ),
+        )),
+        Ok(_) => Ok(()),
+        // not created yet, connecting will fail on its own
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Peer is the sender of a datagram received by the server
+#[derive(Debug)]
+pub struct Peer {
+    // the address responses go to, none for an unnamed socket
+    pub path: Option<PathBuf>,
+    uid: Option<u32>,
+}
+
+// recv_from_peer receives a datagram with the credentials of the process which sent it.
+// They are filled in by the kernel, so a sender can't claim another user
+#[cfg(target_os = "linux")]
+pub async fn recv_from_peer(
+    socket: &UnixDatagram,
+    buf: &mut [u8],
+) -> std::io::Result<(usize, Peer)> {
+    use std::os::fd::AsRawFd;
+    use tokio::io::Interest;
+
+    socket
+        .async_io(Interest::READABLE, || {
+            recv_with_credentials(socket.as_raw_fd(), buf)
+        })
+        .await
+}
+
+// without per datagram credentials the sender is trusted by the owner of its socket file,
+// which relies on the socket directory being writable only by the user running the server
+#[cfg(not(target_os = "linux"))]
+pub async fn recv_from_peer(
+    socket: &UnixDatagram,
+    buf: &mut [u8],
+) -> std::io::Result<(usize, Peer)> {
+    let (size, addr) = socket.recv_from(buf).await?;
+    let path = addr.as_pathname().map(Path::to_path_buf);
+    let uid = path
+        .as_deref()
+        .and_then(|path| fs::symlink_metadata(path).ok())
+        .map(|metadata| metadata.uid());
+
+    Ok((size, Peer { path, uid }))
+}
+
+#[cfg(target_os = "linux")]
+fn recv_with_credentials(fd: std::os::fd::RawFd, buf: &mut [u8]) -> std::io::Result<(usize, Peer)> {
+    use std::ffi::OsStr;
+    use std::mem;
+    use std::os::unix::ffi::OsStrExt;
+
+    // SAFETY: the structs are plain c data, all zeros is a valid value for them
+    let mut addr: libc::sockaddr_un = unsafe { mem::zeroed() };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    // room for one ucred message, u64 keeps it aligned for cmsghdr
+    let mut control = [0u64; 8];
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    msg.msg_name = (&mut addr as *mut libc::sockaddr_un).cast();
+    msg.msg_namelen = mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = mem::size_of_val(&control);
+
+    // SAFETY: every pointer in msg points to a live buffer of the given length
+    let size = unsafe { libc::recvmsg(fd, &mut msg, 0) };
+    if size < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // unnamed and abstract addresses have no path
+    let path_length =
+        (msg.msg_namelen as usize).saturating_sub(mem::size_of::<libc::sa_family_t>());
+    let path_bytes: Vec<u8> = addr.sun_path[..path_length.min(addr.sun_path.len())]
+        .iter()
+        .map(|c| *c as u8)
+        .take_while(|c| *c != 0)
+        .collect();
+    let path = (!path_bytes.is_empty()).then(|| PathBuf::from(OsStr::from_bytes(&path_bytes)));
+
+    let mut uid = None;
+    // SAFETY: the cmsg macros walk the control buffer within msg_controllen
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_CREDENTIALS
+            {
+                let credentials: libc::ucred =
+                    std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast());
+                uid = Some(credentials.uid);
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    Ok((size as usize, Peer { path, uid }))
+}
+
+// enable_peer_credentials asks the kernel to attach the sender credentials to every datagram
+#[cfg(target_os = "linux")]
+fn enable_peer_credentials(socket: &UnixDatagram) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let enable: libc::c_int = 1;
+    // SAFETY: the option value is a c_int of the given size
+    let result = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PASSCRED,
+            (&enable as *const libc::c_int).cast(),
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    match result {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn enable_peer_credentials(_socket: &UnixDatagram) -> std::io::Result<()> {
+    Ok(())
+}
+
+// is_trusted_peer checks the sender of a datagram runs as the user running the server
+pub fn is_trusted_peer(peer: &Peer) -> bool {
+    match peer.uid {
+        Some(uid) if uid == get_current_uid() => true,
+        Some(uid) => {
+            warn!("reject message from {:?}: sent by uid {}", peer.path, uid);
+            false
+        }
+        None => {
+            warn!("reject message from {:?}: no sender credentials", peer.path);
+            false
+        }
+    }
+}
+
+pub fn is_client_address(path: &Path) -> bool {
+    has_socket_name(path, SOCKET_CLIENT_ADDR_INFIX)
+}
+
+pub fn is_subscriber_address(path: &Path) -> bool {
+    has_socket_name(path, SOCKET_SUBSCRIBER_ADDR_INFIX)
+}
+
+// has_socket_name checks the path is a socket of this instance like `<prefix><infix><pid>.sock`
+fn has_socket_name(path: &Path, infix: &str) -> bool {
+    let is_same_dir = path.parent() == get_uds_address(UdsType::Server).parent();
+    let is_socket_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(&format!("{}{}", instance::get().get_prefix(), infix)))
+        .and_then(|name| name.strip_suffix(".sock"))
+        .map(|pid| pid.parse::<u32>().is_ok())
+        .unwrap_or(false);
+
+    is_same_dir && is_socket_name
+}
+
+pub async fn send_to(socket: &UnixDatagram, target: PathBuf, buf: &[u8]) -> std::io::Result<()> {
     let size = buf.len();
     debug!("buf length: {}", size);
     debug!("size / CHUNK: {}", size / CHUNK);
 
-    for i in 0..size / CHUNK + 1 {
-        let (start, end) = (CHUNK * i, CHUNK * (i + 1));
-        let end = if end > size { size } else { end };
-
-        let buf = &buf[start..end];
+    // the empty datagram below is the only one ending the message, even when the size is a
+    // multiple of CHUNK
+    for buf in buf.chunks(CHUNK) {
         debug!("buf length to be sent: {}", buf.len());
         match socket.send_to(buf, &target).await {
             Ok(_) => debug!("sent for loop"),
-            Err(e) => error!("send_to error: {:?}", e),
+            Err(e) => {
+                error!("send_to error: {:?}", e);
+                return Err(e);
+            }
         };
 
         // Wait for certain time due to
@@ -423,6 +986,324 @@ pub async fn send_to(socket: &UnixDatagram, target: PathBuf, buf: &[u8]) {
     let fin = Vec::new();
     match socket.send_to(fin.as_slice(), &target).await {
         Ok(_) => debug! {"sent final"},
-        Err(e) => error!("There was an error sending to the socket: {}", e),
+        Err(e) => {
+            error!("There was an error sending to the socket: {}", e);
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::{
+        get_uds_address, internal, is_client_address, is_subscriber_address, is_trusted_peer,
+        prepare_socket_dir, send_to, Bincodec, MessageRequest, MessageResponse, Peer, TaskRequest,
+        UdsMessage, UdsType, CHUNK,
+    };
+    use crate::command::get_main_command;
+
+    fn message_request() -> impl Strategy<Value = MessageRequest> {
+        let times = (
+            any::<Option<u32>>(),
+            any::<Option<u32>>(),
+            any::<Option<String>>(),
+            any::<Option<String>>(),
+        );
+        let labels = (
+            any::<Vec<String>>(),
+            any::<Option<String>>(),
+            any::<Option<u64>>(),
+        );
+        let filter = (any::<Option<String>>(), any::<Option<String>>());
+        prop_oneof![
+            (times.clone(), labels.clone(), any::<Option<i64>>()).prop_map(
+                |((work, r#break, desciption, preset), (tags, project, task), start_at)| {
+                    MessageRequest::Create {
+                        work,
+                        r#break,
+                        desciption,
+                        preset,
+                        tags,
+                        project,
+                        task,
+                        start_at,
+                    }
+                }
+            ),
+            (times, labels).prop_map(
+                |((work, r#break, desciption, preset), (tags, project, task))| {
+                    MessageRequest::Queue {
+                        work,
+                        r#break,
+                        desciption,
+                        preset,
+                        tags,
+                        project,
+                        task,
+                    }
+                }
+            ),
+            (any::<Option<String>>(), any::<bool>())
+                .prop_map(|(id, all)| MessageRequest::Delete { id, all }),
+            (any::<bool>(), filter.clone()).prop_map(|(show_percentage, (tag, project))| {
+                MessageRequest::List {
+                    show_percentage,
+                    tag,
+                    project,
+                }
+            }),
+            (any::<bool>(), filter).prop_map(|(should_clear, (tag, project))| {
+                MessageRequest::History {
+                    should_clear,
+                    tag,
+                    project,
+                }
+            }),
+            Just(MessageRequest::Test),
+            Just(MessageRequest::Status),
+            Just(MessageRequest::Shutdown),
+            Just(MessageRequest::ReloadConfig),
+            prop_oneof![
+                (any::<String>(), any::<Option<u16>>())
+                    .prop_map(|(title, estimate)| TaskRequest::Add { title, estimate }),
+                any::<bool>().prop_map(|all| TaskRequest::List { all }),
+                any::<u64>().prop_map(|id| TaskRequest::Done { id }),
+                (any::<u64>(), any::<u16>())
+                    .prop_map(|(id, estimate)| TaskRequest::Estimate { id, estimate }),
+            ]
+            .prop_map(MessageRequest::Task),
+            (any::<bool>(), any::<Option<String>>())
+                .prop_map(|(external, note)| MessageRequest::Interrupt { external, note }),
+            any::<Option<String>>().prop_map(|id| MessageRequest::Pause { id }),
+            any::<Option<String>>().prop_map(|id| MessageRequest::Resume { id }),
+            (any::<String>(), any::<String>())
+                .prop_map(|(id, note)| MessageRequest::Note { id, note }),
+            (any::<String>(), any::<u8>())
+                .prop_map(|(id, rating)| MessageRequest::Rate { id, rating }),
+            (any::<String>(), any::<String>())
+                .prop_map(|(from, to)| MessageRequest::Stats { from, to }),
+            (any::<Option<u16>>(), any::<Option<String>>())
+                .prop_map(|(weeks, goal)| MessageRequest::Heatmap { weeks, goal }),
+        ]
+    }
+
+    proptest! {
+        // whatever a peer sends, decoding returns an error instead of panicking
+        #[test]
+        fn test_decode_arbitrary_bytes(bytes in proptest::collection::vec(any::<u8>(), 0..512)) {
+            let _ = UdsMessage::decode(&bytes);
+            let _ = MessageResponse::decode(&bytes);
+            let _ = internal::Message::decode(&bytes);
+        }
+
+        #[test]
+        fn test_request_round_trip(request in message_request()) {
+            let encoded = UdsMessage::Public(request).encode().unwrap();
+            let decoded = UdsMessage::decode(&encoded).unwrap();
+            let re_encoded = decoded.encode().unwrap();
+            prop_assert_eq!(encoded, re_encoded);
+        }
+
+        #[test]
+        fn test_truncated_request(request in message_request(), cut in any::<prop::sample::Index>()) {
+            let encoded = UdsMessage::Public(request).encode().unwrap();
+            let _ = UdsMessage::decode(&encoded[..cut.index(encoded.len())]);
+        }
+    }
+
+    #[test]
+    fn test_decode_huge_length_prefix() {
+        // Public(Create { work: None, break: None, desciption: Some(<2^62 bytes>) })
+        let mut bytes = vec![0, 0, 0, 0, 1, 0xfd];
+        bytes.extend_from_slice(&(1u64 << 62).to_le_bytes());
+        assert!(UdsMessage::decode(&bytes).is_err());
+
+        // MessageResponse { body: [<2^62 bytes>], .. }
+        let mut bytes = vec![1, 0xfd];
+        bytes.extend_from_slice(&(1u64 << 62).to_le_bytes());
+        assert!(MessageResponse::decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_request_args() {
+        let request = MessageRequest::Queue {
+            work: Some(90),
+            r#break: None,
+            desciption: Some(String::from("-review  the report")),
+            preset: None,
+            tags: vec![String::from("client")],
+            project: None,
+            task: None,
+        };
+        let args = Vec::<String>::from(request);
+        let matches = get_main_command().try_get_matches_from(args).unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(
+            sub_matches.get_one::<String>("description").unwrap(),
+            "-review  the report"
+        );
+        assert_eq!(sub_matches.get_one::<String>("work").unwrap(), "90s");
+
+        // free text starting with a dash stays text
+        let request = MessageRequest::Interrupt {
+            external: true,
+            note: Some(String::from("-h phone")),
+        };
+        let matches = get_main_command()
+            .try_get_matches_from(Vec::<String>::from(request))
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert!(sub_matches.get_flag("external"));
+        assert_eq!(
+            crate::command::util::parse_note(sub_matches).unwrap(),
+            Some(String::from("-h phone"))
+        );
+
+        let request = MessageRequest::Note {
+            id: String::from("1b4e"),
+            note: String::from("--all done"),
+        };
+        let matches = get_main_command()
+            .try_get_matches_from(Vec::<String>::from(request))
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(sub_matches.get_one::<String>("id").unwrap(), "1b4e");
+        assert_eq!(
+            crate::command::util::parse_note(sub_matches).unwrap(),
+            Some(String::from("--all done"))
+        );
+
+        let request = MessageRequest::Task(TaskRequest::Add {
+            title: String::from("-e 3 write"),
+            estimate: Some(2),
+        });
+        let matches = get_main_command()
+            .try_get_matches_from(Vec::<String>::from(request))
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        let (_, add_matches) = sub_matches.subcommand().unwrap();
+        assert_eq!(add_matches.get_one::<u16>("estimate"), Some(&2));
+        assert_eq!(
+            add_matches
+                .get_many::<String>("title")
+                .unwrap()
+                .collect::<Vec<&String>>(),
+            vec!["-e 3 write"]
+        );
+    }
+
+    #[test]
+    fn test_client_address() {
+        let client = get_uds_address(UdsType::Client(42));
+        assert!(is_client_address(&client));
+        assert!(!is_subscriber_address(&client));
+        assert!(is_subscriber_address(&get_uds_address(
+            UdsType::Subscriber(42)
+        )));
+
+        let server = get_uds_address(UdsType::Server);
+        assert!(!is_client_address(&server));
+        assert!(!is_client_address(
+            &server.with_file_name("rust-cli-pomodoro-client-x.sock")
+        ));
+        assert!(!is_client_address(
+            &std::env::temp_dir().join(client.file_name().unwrap())
+        ));
+    }
+
+    #[test]
+    fn test_prepare_socket_dir() {
+        let base = std::env::temp_dir().join(format!("pomodoro-ipc-test-{}", std::process::id()));
+        let dir = base.join("sockets");
+
+        prepare_socket_dir(&dir, true).unwrap();
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        // the default directory is fixed, a custom one is refused
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(prepare_socket_dir(&dir, false).is_err());
+        prepare_socket_dir(&dir, true).unwrap();
+        let mode = fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_to_chunks() {
+        let dir = std::env::temp_dir().join(format!("pomodoro-send-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("receiver.sock");
+        let receiver = tokio::net::UnixDatagram::bind(&target).unwrap();
+        let sender = tokio::net::UnixDatagram::unbound().unwrap();
+
+        let mut buf = [0u8; CHUNK];
+        for (size, expected) in [
+            (CHUNK, vec![CHUNK, 0]),
+            (2 * CHUNK + 1, vec![CHUNK, CHUNK, 1, 0]),
+            (3, vec![3, 0]),
+            (0, vec![0]),
+        ] {
+            send_to(&sender, target.clone(), &vec![7u8; size])
+                .await
+                .unwrap();
+
+            let mut sizes = Vec::new();
+            for _ in 0..expected.len() {
+                sizes.push(receiver.recv(&mut buf).await.unwrap());
+            }
+            assert_eq!(sizes, expected);
+        }
+        // nothing is left over to be read as the end of the next message
+        assert!(receiver.try_recv(&mut buf).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_recv_from_peer() {
+        use super::{enable_peer_credentials, get_current_uid, recv_from_peer};
+        use tokio::net::UnixDatagram;
+
+        let dir = std::env::temp_dir().join(format!("pomodoro-peer-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let server = UnixDatagram::bind(dir.join("server.sock")).unwrap();
+        enable_peer_credentials(&server).unwrap();
+        let client_path = dir.join("client.sock");
+        let client = UnixDatagram::bind(&client_path).unwrap();
+
+        client
+            .send_to(b"ping", dir.join("server.sock"))
+            .await
+            .unwrap();
+        let mut buf = [0u8; CHUNK];
+        let (size, peer) = recv_from_peer(&server, &mut buf).await.unwrap();
+        assert_eq!(&buf[..size], b"ping");
+        assert_eq!(peer.path.as_deref(), Some(client_path.as_path()));
+        assert!(is_trusted_peer(&peer));
+
+        // the credentials come from the kernel, not from the socket file
+        let other = Peer {
+            path: Some(client_path),
+            uid: Some(get_current_uid() + 1),
+        };
+        assert!(!is_trusted_peer(&other));
+        let unnamed = UnixDatagram::unbound().unwrap();
+        unnamed
+            .send_to(b"ping", dir.join("server.sock"))
+            .await
+            .unwrap();
+        let (_, peer) = recv_from_peer(&server, &mut buf).await.unwrap();
+        assert!(peer.path.is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -1,3 +1,4 @@
+use crate::command::action::ActionType;
 use crate::{InputSource, UserInput};
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
@@ -28,14 +29,20 @@ pub fn handle(tx: Sender<UserInput>) -> Result<JoinHandle<()>, Box<dyn Error + S
 
                     let _ = tx
                         .send(UserInput {
-                            input: line,
+                            input: line.split_whitespace().map(String::from).collect(),
                             source: InputSource::StandardInput,
                         })
                         .await;
                 }
-                // handles the CTRL + C event
+                // handles the CTRL + C event, stopping the server gracefully
                 Err(ReadlineError::Interrupted) => {
                     println!("CTRL-C");
+                    let _ = tx
+                        .send(UserInput {
+                            input: vec![String::from(ActionType::Shutdown)],
+                            source: InputSource::StandardInput,
+                        })
+                        .await;
                     break;
                 }
                 Err(ReadlineError::Eof) => {
@@ -1,20 +1,28 @@
+use std::fs::File;
+
 fn get_binary_name() -> String {
     let binary_name = env!("CARGO_BIN_NAME");
     binary_name.to_string()
 }
 
-pub fn initialize_logging() {
+// logs go to stderr, or to the given file when running as daemon
+pub fn initialize_logging(log_file: Option<File>) {
     let package_name = &get_binary_name();
 
+    let mut builder = env_logger::Builder::from_default_env();
     if cfg!(debug_assertions) {
-        env_logger::Builder::from_default_env()
-            .filter(Some(package_name), log::LevelFilter::Debug)
-            .init();
+        builder.filter(Some(package_name), log::LevelFilter::Debug);
     } else {
-        env_logger::Builder::from_default_env()
-            .filter(Some(package_name), log::LevelFilter::Info)
-            .init();
+        builder.filter(Some(package_name), log::LevelFilter::Info);
+    }
+
+    if let Some(file) = log_file {
+        builder
+            .target(env_logger::Target::Pipe(Box::new(file)))
+            .write_style(env_logger::WriteStyle::Never);
     }
+
+    builder.init();
 }
 
 #[cfg(test)]
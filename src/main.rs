@@ -1,12 +1,15 @@
 use chrono::prelude::*;
+use clap::ArgMatches;
 use clap_complete::generate;
 use gluesql::prelude::{Glue, MemoryStorage};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
 use std::io::{self};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::oneshot;
 use tokio::time::sleep;
 use tokio::{net::UnixDatagram, sync::mpsc};
 use tokio::{sync::mpsc::Sender, task::JoinHandle};
@@ -15,24 +18,44 @@ mod command;
 mod database;
 mod notification;
 use database as db;
+mod config_validation;
+mod config_watcher;
 mod configuration;
+mod credential;
+mod daemon;
 mod error;
+mod event;
+mod goal;
+mod heatmap;
+mod http_api;
+mod instance;
 mod ipc;
 mod line_handler;
 mod logging;
 mod report;
 mod sled_databbase;
-
-use crate::error::ConfigurationError;
-use crate::ipc::{create_client_uds, create_server_uds, Bincodec, MessageRequest, MessageResponse};
-use crate::notification::notify::{notify_break, notify_work};
+mod stats;
+mod status;
+mod task;
+
+use crate::daemon::{DaemonOptions, PidFile, SpawnOptions};
+use crate::error::{ConfigurationError, UdsHandlerError};
+use crate::event::{Event, EventKind, EventSender};
+use crate::goal::{get_next_reminder, GoalProgress};
+use crate::ipc::{
+    create_client_uds, create_server_uds, create_subscriber_uds, Bincodec, MessageResponse,
+};
+use crate::notification::notify::{
+    notify_break, notify_goal_missed, notify_goal_reached, notify_missed, notify_work,
+};
 use crate::sled_databbase::{NotificationSled, SledStore};
+use crate::status::Phase;
 use crate::{
     command::{handler, util, CommandType},
     ipc::{get_uds_address, UdsType},
 };
 use crate::{
-    configuration::{get_configuration, Configuration},
+    configuration::{get_configuration, get_configuration_path, run_blocking, Configuration},
     ipc::UdsMessage,
 };
 
@@ -44,132 +67,80 @@ pub type TaskMap = HashMap<uuid::Uuid, JoinHandle<()>>;
 pub type ArcGlue = Arc<Mutex<Glue<MemoryStorage>>>;
 pub type ArcTaskMap = Arc<Mutex<TaskMap>>;
 
+// the http api awaits the handled result of its request on this channel
+pub type HttpResponder = oneshot::Sender<Result<MessageResponse, String>>;
+
 #[derive(Debug)]
 pub struct UserInput {
-    pub input: String,
-    // pub oneshot_tx: oneshot::Sender<String>,
+    // the command line split in arguments, without the binary name
+    pub input: Vec<String>,
     pub source: InputSource,
 }
 
 #[derive(Debug)]
 pub enum InputSource {
     StandardInput,
-    UnixDomainSocket,
+    // the address of the client socket the response goes back to
+    UnixDomainSocket(PathBuf),
+    Http(HttpResponder),
+    // the configuration file changed, the output is only logged
+    ConfigurationWatcher,
 }
 
 #[tokio::main]
 async fn main() {
     match run().await {
         Ok(_) => {
-            println!("Program completed successfully.");
+            // keep stdout clean for `--output json|csv`
+            debug!("Program completed successfully.");
             std::process::exit(0);
         }
         Err(e) => {
             eprintln!("Error: {}", e);
-            println!("Exiting the program due to an error.");
+            eprintln!("Exiting the program due to an error.");
             std::process::exit(1);
         }
     }
 }
 
 async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    logging::initialize_logging();
+    let matches = command::get_start_and_uds_client_command().get_matches();
+    let log_file = match get_daemon_log_file(&matches) {
+        Some(path) => Some(daemon::open_log_file(Path::new(path))?),
+        None => None,
+    };
+    logging::initialize_logging(log_file);
+    instance::initialize(instance::Instance::from_matches(&matches)?);
 
     debug!("debug test, start pomodoro... 1.5.4");
-    let command_type = detect_command_type().await?;
+    let spawn_options = SpawnOptions::from_matches(&matches);
+    let command_type = detect_command_type(matches).await?;
     match command_type {
-        CommandType::StartUp(config) => {
-            info!("Starting server...");
-
-            let path =
-                Path::new("/home/sinh/.local/share/applications/sinh-x/pomodoro/sled_databbase");
-            if let Some(parent_path) = path.parent() {
-                fs::create_dir_all(parent_path)?;
-            } else {
-                panic!(
-                    "could not create parent directory for sled database: {}",
-                    path.display()
-                )
-            }
-            let sled_store = SledStore::new(&path).unwrap();
-
-            let glue = initialize_db().await;
-            let hash_map: Arc<Mutex<TaskMap>> = Arc::new(Mutex::new(HashMap::new()));
-            let (user_input_tx, mut user_input_rx) = mpsc::channel::<UserInput>(64);
-
-            // Start handling stdin input in a separate task
-            let stdin_tx = user_input_tx.clone();
-            let _input_handle = match line_handler::handle(stdin_tx) {
-                Ok(handle) => Some(handle),
-                Err(_) => None, // Ignore errors from stdin
-            };
-            // Start handling UDS input
-            let uds_input_tx = user_input_tx.clone();
-            let server_uds_option = create_server_uds().await.unwrap();
-            let server_tx = match server_uds_option {
-                Some(uds) => {
-                    let server_uds = Arc::new(uds);
-                    let (server_rx, server_tx) = (server_uds.clone(), server_uds.clone());
-                    let _uds_input_handle =
-                        spawn_uds_input_handler(uds_input_tx, server_tx, server_rx);
-                    Some(server_uds)
-                }
-                None => {
-                    error!("main:Failed to create or connect to server UDS");
-                    std::process::exit(1);
-                }
-            };
-
-            match sled_store.list_notifications() {
-                Ok(active_notifications) => {
-                    for current_notification in active_notifications {
-                        hash_map.lock().unwrap().insert(
-                            current_notification.get_id(),
-                            spawn_notification(
-                                config.clone(),
-                                hash_map.clone(),
-                                &sled_store,
-                                current_notification,
-                            ),
-                        );
-                    }
-                }
-                Err(e) => {
-                    debug!("There was an error spawn existing notifications: {}", e)
-                }
-            };
-
-            // Main loop to handle user input
-            debug!("Main loop to handle user input");
-            loop {
-                debug!("Server is alive");
-                while let Some(user_input) = user_input_rx.recv().await {
-                    debug!("Server is alive inside while");
-                    match handle_user_input(
-                        user_input,
-                        &hash_map,
-                        &glue,
-                        &config,
-                        &server_tx,
-                        &sled_store,
-                    )
-                    .await
-                    {
-                        Ok(_) => {}
-                        Err(e) => debug!("There was an error handling the user input: {}", e),
-                    }
-                }
-            }
+        CommandType::StartUp(config) => run_server(config, None).await?,
+        CommandType::Daemon(config, options) => run_server(config, Some(options)).await?,
+        CommandType::SystemdUnit(sub_matches) => {
+            print!("{}", daemon::get_systemd_unit(&sub_matches)?);
         }
         CommandType::UdsClient(matches) => {
             debug!("CommandType::UdsClient");
+            daemon::ensure_server_running(&spawn_options).await?;
             let socket = create_client_uds().await?;
             handler::uds_client::handle(matches, socket).await?;
         }
+        CommandType::Watch => {
+            debug!("CommandType::Watch");
+            daemon::ensure_server_running(&spawn_options).await?;
+            let socket = create_subscriber_uds().await?;
+            handler::uds_client::handle_watch(socket).await?;
+        }
+        CommandType::Config(sub_matches) => handler::config::handle(&sub_matches)?,
         CommandType::AutoComplete(sub_matches) => {
             if sub_matches.contains_id("shell") {
                 if let Some(shell) = util::parse_shell(&sub_matches) {
-                    let mut main_command = command::get_main_command();
+                    let mut main_command = command::with_preset_names(
+                        command::get_main_command(),
+                        get_preset_names(&sub_matches),
+                    );
                     let bin_name = main_command.get_name().to_string();
                     let mut stdout = std::io::stdout();
                     generate(shell, &mut main_command, bin_name, &mut stdout);
@@ -185,67 +156,374 @@ async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     Ok(())
 }
 
+// run_server serves the notifications. The daemon runs without the stdin prompt
+async fn run_server(
+    mut config: Arc<Configuration>,
+    daemon_options: Option<DaemonOptions>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("Starting server...");
+
+    // claimed before opening the database so a second daemon fails early, removed on return.
+    // The default pid file is in the socket directory, prepared first
+    let _pid_file = match &daemon_options {
+        Some(options) => {
+            ipc::prepare_instance_dir()?;
+            Some(PidFile::create(&options.pid_file)?)
+        }
+        None => None,
+    };
+
+    let path = instance::get().get_database_path();
+    if let Some(parent_path) = path.parent() {
+        fs::create_dir_all(parent_path)?;
+    }
+    // fails when another server holds the database lock
+    let sled_store = SledStore::new(&path)?;
+
+    let glue = initialize_db().await;
+    let hash_map: Arc<Mutex<TaskMap>> = Arc::new(Mutex::new(HashMap::new()));
+    let events = event::create_event_bus();
+    let (user_input_tx, mut user_input_rx) = mpsc::channel::<UserInput>(64);
+
+    // Start handling stdin input in a separate task, the daemon only listens on uds and http
+    let _input_handle = match daemon_options {
+        Some(_) => {
+            util::disable_prompt();
+            None
+        }
+        None => {
+            let stdin_tx = user_input_tx.clone();
+            match line_handler::handle(stdin_tx) {
+                Ok(handle) => Some(handle),
+                Err(_) => None, // Ignore errors from stdin
+            }
+        }
+    };
+    // Reload the configuration when its file changes
+    let _config_watcher_handle = config
+        .get_path()
+        .clone()
+        .map(|path| config_watcher::handle(path, user_input_tx.clone()));
+    // Start handling http api input, only served when a token is configured
+    if let Some(token) = config.get_http_token() {
+        let _http_handle = http_api::spawn_server(
+            config.get_http_port(),
+            token.clone(),
+            user_input_tx.clone(),
+            events.clone(),
+        );
+    }
+
+    // Start handling UDS input
+    let uds_input_tx = user_input_tx.clone();
+    let server_uds_option = create_server_uds().await?;
+    let server_tx = match server_uds_option {
+        Some(uds) => {
+            let server_uds = Arc::new(uds);
+            let (server_rx, server_tx) = (server_uds.clone(), server_uds.clone());
+            let _uds_input_handle =
+                spawn_uds_input_handler(uds_input_tx, server_tx, server_rx, events.clone());
+            Some(server_uds)
+        }
+        None => {
+            error!("main:Failed to create or connect to server UDS");
+            std::process::exit(1);
+        }
+    };
+
+    reconcile_notifications(&config, &sled_store).await;
+
+    match sled_store.list_notifications() {
+        Ok(active_notifications) => {
+            // paused ones get their timers back on `resume`
+            for current_notification in active_notifications.into_iter().filter(|n| !n.is_paused())
+            {
+                hash_map.lock().unwrap().insert(
+                    current_notification.get_id(),
+                    spawn_notification(
+                        config.clone(),
+                        hash_map.clone(),
+                        &sled_store,
+                        events.clone(),
+                        current_notification,
+                    ),
+                );
+            }
+        }
+        Err(e) => error!("failed to respawn existing notifications: {}", e),
+    };
+
+    // Main loop to handle user input until SIGTERM, SIGINT or `shutdown` command
+    debug!("Main loop to handle user input");
+    let shutdown = daemon::wait_for_shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        debug!("Server is alive");
+        let reminder = config
+            .get_daily_goal_reminder()
+            .and_then(|reminder| get_next_reminder(reminder, Local::now()));
+        tokio::select! {
+            result = &mut shutdown => {
+                result?;
+                break;
+            }
+            _ = wait_for_reminder(reminder) => remind_daily_goal(&config, &sled_store),
+            Some(user_input) = user_input_rx.recv() => {
+                debug!("Server is alive inside select");
+                match handle_user_input(
+                    user_input,
+                    &hash_map,
+                    &glue,
+                    &mut config,
+                    &server_tx,
+                    &sled_store,
+                    &events,
+                )
+                .await
+                {
+                    Ok(true) => break,
+                    Ok(false) => {}
+                    Err(e) => debug!("There was an error handling the user input: {}", e),
+                }
+            }
+        }
+    }
+
+    // the next start up reconciles notifications which end before it
+    if let Err(e) = sled_store.record_server_stop(Utc::now()) {
+        error!("failed to record server stop: {}", e);
+    }
+
+    // the next server start must not mistake the socket file for a running server
+    if let Err(e) = fs::remove_file(get_uds_address(UdsType::Server)) {
+        debug!("failed to remove server socket: {}", e);
+    }
+    info!("Server stopped");
+
+    Ok(())
+}
+
+async fn wait_for_reminder(reminder: Option<DateTime<Local>>) {
+    match reminder {
+        Some(at) => sleep((at - Local::now()).to_std().unwrap_or_default()).await,
+        None => std::future::pending().await,
+    }
+}
+
+// remind_daily_goal sends the end of day reminder when the daily goal is not reached yet
+fn remind_daily_goal(config: &Arc<Configuration>, sled_store: &SledStore) {
+    let Some(goal) = config.get_daily_goal() else {
+        return;
+    };
+    let progress = match sled_store.list_all_notifications() {
+        Ok(notifications) => GoalProgress::today(goal, &notifications, Utc::now()),
+        Err(e) => {
+            error!("failed to read the daily goal progress: {}", e);
+            return;
+        }
+    };
+    if progress.is_reached() {
+        return;
+    }
+
+    let config = config.clone();
+    tokio::spawn(async move {
+        if let Ok(report) = notify_goal_missed(&config, &progress).await {
+            info!("\n{}", report);
+            util::write_output(&mut io::stdout());
+        }
+    });
+}
+
+// reconcile_notifications marks notifications which ended while the server was down,
+// and sends a summary of them when `missed_notification_summary` is set
+async fn reconcile_notifications(config: &Arc<Configuration>, sled_store: &SledStore) {
+    let reconciled = match sled_store.reconcile(Utc::now()) {
+        Ok(reconciled) => reconciled,
+        Err(e) => {
+            error!("failed to reconcile notifications: {}", e);
+            return;
+        }
+    };
+
+    if reconciled.is_empty() {
+        return;
+    }
+
+    let mut lines = Vec::new();
+    for notification in reconciled.iter() {
+        let description = match notification.get_labels() {
+            Some(labels) => format!("{} [{}]", notification.description, labels),
+            None => notification.description.to_string(),
+        };
+        let line = format!(
+            "{} ({}): {}",
+            description,
+            notification.get_id(),
+            notification
+                .outcome
+                .map(|outcome| outcome.to_string())
+                .unwrap_or_default()
+        );
+        info!("notification ended while the server was down, {}", line);
+        lines.push(line);
+    }
+
+    if config.get_missed_notification_summary() {
+        let message = format!(
+            "{} pomodoro(s) ended while the server was down\n{}",
+            reconciled.len(),
+            lines.join("\n")
+        );
+        // don't hold up the start up on slow notification channels
+        let config = config.clone();
+        tokio::spawn(async move {
+            if let Ok(report) = notify_missed(&config, &message).await {
+                info!("\n{}", report);
+            }
+        });
+    }
+}
+
 async fn handle_user_input(
     user_input: UserInput,
     hash_map: &Arc<Mutex<TaskMap>>,
     glue: &ArcGlue,
-    config: &Arc<Configuration>,
+    config: &mut Arc<Configuration>,
     server_tx: &Option<Arc<UnixDatagram>>,
     sled_store: &SledStore,
-) -> Result<(), Box<dyn Error>> {
-    let input = user_input.input.as_str();
+    events: &EventSender,
+) -> Result<bool, Box<dyn Error>> {
+    let UserInput { input, source } = user_input;
     debug!("Input: {:?}", input);
-
-    match handler::user_input::handle(input, hash_map, glue, config, sled_store).await {
-        Ok(mut output) => match user_input.source {
-            InputSource::StandardInput => {}
-            InputSource::UnixDomainSocket => {
-                if let Some(ref server_tx) = server_tx {
-                    let client_addr = get_uds_address(UdsType::Client);
-                    ipc::send_to(
-                        server_tx,
-                        client_addr,
-                        MessageResponse::new(output.take_body())
-                            .encode()?
-                            .as_slice(),
-                    )
-                    .await;
+    // the prompt belongs to the terminal, input from clients doesn't print it again
+    let is_standard_input = matches!(source, InputSource::StandardInput);
+
+    // true when the server should stop after this input
+    let should_shutdown = match handler::user_input::handle(
+        &input, &source, hash_map, glue, config, sled_store, events,
+    )
+    .await
+    {
+        Ok(mut output) => {
+            let should_shutdown = output.is_shutdown_requested();
+            match source {
+                InputSource::StandardInput | InputSource::ConfigurationWatcher => {}
+                InputSource::UnixDomainSocket(client_addr) => {
+                    if let Some(ref server_tx) = server_tx {
+                        let _ = ipc::send_to(
+                            server_tx,
+                            client_addr,
+                            MessageResponse::with_data(output.take_body(), output.take_data())
+                                .encode()?
+                                .as_slice(),
+                        )
+                        .await;
+                    }
+                }
+                InputSource::Http(responder) => {
+                    let response =
+                        MessageResponse::with_data(output.take_body(), output.take_data());
+                    if responder.send(Ok(response)).is_err() {
+                        debug!("http request was dropped before the response");
+                    }
                 }
             }
-        },
+            should_shutdown
+        }
         Err(e) => {
             debug!("There was an error analyzing the input: {}", e);
-            if let Some(ref server_tx) = server_tx {
-                let client_addr = get_uds_address(UdsType::Client);
-                if let Ok(encoded) = MessageResponse::new(vec![format!(
-                    "There was an error analyzing the input: {}",
-                    e
-                )])
-                .encode()
-                {
-                    let _ = ipc::send_to(server_tx, client_addr, encoded.as_slice()).await;
-                } else {
-                    debug!("Error encoding message response");
+            let message = format!("There was an error analyzing the input: {}", e);
+            match source {
+                InputSource::Http(responder) => {
+                    if responder.send(Err(message)).is_err() {
+                        debug!("http request was dropped before the response");
+                    }
+                }
+                InputSource::ConfigurationWatcher => {
+                    error!("keep the current configuration: {}", e);
+                }
+                InputSource::StandardInput => {}
+                InputSource::UnixDomainSocket(client_addr) => {
+                    if let Some(ref server_tx) = server_tx {
+                        if let Ok(encoded) = MessageResponse::new(vec![message]).encode() {
+                            let _ = ipc::send_to(server_tx, client_addr, encoded.as_slice()).await;
+                        } else {
+                            debug!("Error encoding message response");
+                        }
+                    }
                 }
             }
+            false
         }
+    };
+
+    debug!("Handled input: {:?}", input);
+    if !should_shutdown && is_standard_input {
+        util::print_start_up();
     }
 
-    debug!("Handled input: {:?}", user_input);
-    util::print_start_up();
+    Ok(should_shutdown)
+}
 
-    Ok(())
+// logging is set up before the configuration is read, so the log file comes from the raw matches
+fn get_daemon_log_file(matches: &ArgMatches) -> Option<&String> {
+    matches
+        .subcommand_matches(command::DAEMON)
+        .filter(|sub_matches| !sub_matches.get_flag("systemd-unit"))
+        .and_then(|sub_matches| sub_matches.get_one::<String>("log-file"))
 }
 
-async fn detect_command_type() -> Result<CommandType, ConfigurationError> {
-    let matches = command::get_start_and_uds_client_command().get_matches();
+// get_preset_names reads the presets for shell completion, without a configuration there are none
+fn get_preset_names(matches: &ArgMatches) -> Vec<String> {
+    let path = match get_configuration_path(matches) {
+        Ok(Some(path)) => path,
+        _ => return vec![],
+    };
+
+    // completion only needs the names, the secrets are not read
+    match configuration::parse_configuration_file(&path) {
+        Ok(config) => config
+            .get_presets()
+            .into_iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        Err(e) => {
+            debug!("failed to read presets from {:?}: {}", path, e);
+            vec![]
+        }
+    }
+}
+
+// read_configuration loads the server configuration, credential commands run off the runtime
+async fn read_configuration(
+    matches: &ArgMatches,
+) -> Result<Arc<Configuration>, ConfigurationError> {
+    let matches = matches.clone();
+    run_blocking(move || get_configuration(&matches)).await
+}
+
+async fn detect_command_type(matches: ArgMatches) -> Result<CommandType, ConfigurationError> {
     debug!("handle_uds_client_command, matches: {:?}", &matches);
 
     let command_type = match matches.subcommand().is_none() {
-        true => CommandType::StartUp(get_configuration(&matches)?),
+        true => CommandType::StartUp(read_configuration(&matches).await?),
         false => {
             if let Some(val) = matches.subcommand_matches("completion") {
                 CommandType::AutoComplete(val.to_owned())
+            } else if let Some(val) = matches.subcommand_matches(command::CONFIG) {
+                CommandType::Config(val.to_owned())
+            } else if matches.subcommand_matches("watch").is_some() {
+                CommandType::Watch
+            } else if let Some(val) = matches.subcommand_matches(command::DAEMON) {
+                if val.get_flag("systemd-unit") {
+                    CommandType::SystemdUnit(val.to_owned())
+                } else {
+                    CommandType::Daemon(
+                        read_configuration(val).await?,
+                        DaemonOptions::from_matches(val),
+                    )
+                }
             } else {
                 CommandType::UdsClient(matches)
             }
@@ -266,15 +544,36 @@ async fn initialize_db() -> Arc<Mutex<Glue<MemoryStorage>>> {
 pub fn spawn_notification(
     configuration: Arc<Configuration>,
     _hash_map: Arc<Mutex<TaskMap>>,
-    _sled_store: &SledStore,
+    sled_store: &SledStore,
+    events: EventSender,
     notification: NotificationSled,
 ) -> JoinHandle<()> {
+    let sled_store = sled_store.clone();
     tokio::spawn(async move {
-        let (id, _, work_time, break_time, _, _, _) = notification.get_values();
+        let (id, _, work_seconds, break_seconds, _, _, _) = notification.get_values();
+        let labels = notification.get_labels();
         let notify_time_min = -10;
         let notify_time_max = 10;
 
-        if work_time > 0 {
+        // queued notification, wait until it starts
+        let st = (notification.get_start_at() - Utc::now()).num_milliseconds();
+        if st > 0 {
+            debug!(
+                "spawn_notification: id ({}) sleep {:?} millis until start",
+                id, st
+            );
+            sleep(tokio::time::Duration::from_millis(st as u64)).await;
+            event::publish(
+                &events,
+                Event::new(
+                    EventKind::Started,
+                    &notification,
+                    event::get_starting_phase(&notification),
+                ),
+            );
+        }
+
+        if work_seconds > 0 {
             let duration = notification.work_expired_at - Utc::now();
             let wt = duration.num_seconds().max(0) as u64;
             match wt {
@@ -282,25 +581,41 @@ pub fn spawn_notification(
                 1.. => {
                     debug!(
                         "spawn_notification: id ({}), work time ({}) sleep {:?} secs",
-                        id, work_time, wt
+                        id, work_seconds, wt
                     );
                     sleep(tokio::time::Duration::from_secs(wt)).await;
+
+                    let (kind, phase) = if break_seconds > 0 {
+                        (EventKind::PhaseChanged, Phase::Break)
+                    } else {
+                        (EventKind::Completed, Phase::Idle)
+                    };
+                    event::publish(&events, Event::new(kind, &notification, phase));
+
                     let time_diff = notification.work_expired_at - Utc::now(); // TODO(young): handle notify report err
                     let time_diff = time_diff.num_seconds();
                     if time_diff >= notify_time_min && time_diff <= notify_time_max {
-                        let result = notify_work(&configuration).await;
+                        let result = notify_work(&configuration, labels.as_deref()).await;
                         if let Ok(report) = result {
                             info!("\n{}", report);
                             debug!("spawn_notification: Notification report generated");
+                            if configuration.get_review_prompt() && util::is_prompt_enabled() {
+                                let short_id = &id.to_string()[..8];
+                                println!(
+                                    "How did it go? `note {} <text>`, `rate {} <1-5>`",
+                                    short_id, short_id
+                                );
+                            }
                             util::write_output(&mut io::stdout());
                         }
+                        notify_daily_goal(&configuration, &sled_store, &notification).await;
                     }
                 }
             }
             debug!("spawn_notification: id ({}) work time done!", id);
         }
 
-        if break_time > 0 {
+        if break_seconds > 0 {
             let duration = notification.break_expired_at - Utc::now();
             let bt = duration.num_seconds().max(0) as u64;
             match bt {
@@ -308,14 +623,20 @@ pub fn spawn_notification(
                 1.. => {
                     debug!(
                         "spawn_notification: id ({}), break time ({}) sleep {:?} secs",
-                        id, break_time, bt
+                        id, break_seconds, bt
                     );
                     sleep(tokio::time::Duration::from_secs(bt)).await;
+
+                    event::publish(
+                        &events,
+                        Event::new(EventKind::Completed, &notification, Phase::Idle),
+                    );
+
                     let time_diff = notification.work_expired_at - Utc::now(); // TODO(young): handle notify report err
                     let time_diff = time_diff.num_seconds();
                     if time_diff >= notify_time_min && time_diff <= notify_time_max {
                         // TODO(young): handle notify report err
-                        let result = notify_break(&configuration).await;
+                        let result = notify_break(&configuration, labels.as_deref()).await;
                         if let Ok(report) = result {
                             info!("\n{}", report);
                             debug!("spawn_notification: Notification report generated");
@@ -329,56 +650,178 @@ pub fn spawn_notification(
     })
 }
 
+// notify_daily_goal celebrates once a day, when the given pomodoro is the one reaching the goal.
+// The work timer may wake up a bit early, the progress is taken at the end of the work time
+async fn notify_daily_goal(
+    configuration: &Arc<Configuration>,
+    sled_store: &SledStore,
+    notification: &NotificationSled,
+) {
+    let Some(goal) = configuration.get_daily_goal() else {
+        return;
+    };
+    let reached = sled_store
+        .list_all_notifications()
+        .ok()
+        .and_then(|notifications| {
+            GoalProgress::reached_with(
+                goal,
+                &notifications,
+                notification.id,
+                notification.work_expired_at,
+            )
+        });
+
+    if let Some(progress) = reached {
+        if let Ok(report) = notify_goal_reached(configuration, &progress).await {
+            info!("\n{}", report);
+            util::write_output(&mut io::stdout());
+        }
+    }
+}
+
+// requests are sent in one datagram, a longer one is truncated and fails to decode
+const UDS_REQUEST_BUFFER_LENGTH: usize = 65_536;
+
 fn spawn_uds_input_handler(
     uds_tx: Sender<UserInput>,
     server_tx: Arc<UnixDatagram>,
     server_rx: Arc<UnixDatagram>,
+    events: EventSender,
 ) -> JoinHandle<Result<(), Box<dyn std::error::Error + Send + Sync>>> {
     tokio::spawn(async move {
         let rx = server_rx;
-        let mut buf = vec![0u8; 256];
+        let mut buf = vec![0u8; UDS_REQUEST_BUFFER_LENGTH];
         debug!("rx is initialized successfully");
         loop {
             debug!("inside unix domain socket task");
-            // TODO(young) handle result
-            let (size, addr) = rx.recv_from(&mut buf).await.unwrap();
-            debug!("size: {:?}, addr: {:?}", size, addr);
+            let (size, peer) = match ipc::recv_from_peer(&rx, &mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    error!("failed to receive from the server socket: {}", e);
+                    // don't spin when the socket keeps failing
+                    sleep(tokio::time::Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            debug!("size: {:?}, peer: {:?}", size, peer);
 
-            if let Some(path) = addr.as_pathname() {
+            let path = match peer.path.as_deref() {
+                Some(path) => {
+                    if !ipc::is_client_address(path) && !ipc::is_subscriber_address(path) {
+                        debug!("addr is different");
+                        continue;
+                    }
+                    if !ipc::is_trusted_peer(&peer) {
+                        continue;
+                    }
+                    path.to_path_buf()
+                }
                 // ignore request from unnamed address
-                if path != get_uds_address(ipc::UdsType::Client).as_path() {
-                    debug!("addr is different");
+                None => {
+                    debug!("addr is unnamed");
                     continue;
                 }
+            };
+
+            match handle_uds_datagram(&buf[..size], &path, &uds_tx, &server_tx, &events).await {
+                Ok(_) => {}
+                Err(UdsHandlerError::ChannelClosed) => {
+                    return Err(UdsHandlerError::ChannelClosed.into());
+                }
+                Err(UdsHandlerError::DecodeFailed(e)) => {
+                    warn!("invalid request from {:?}: {}", path, e);
+                    // the client waits for a response, tell it what went wrong
+                    let message = format!("Server could not decode the request: {}", e);
+                    if let Ok(encoded) = MessageResponse::new(vec![message]).encode() {
+                        let _ = ipc::send_to(&server_tx, path, encoded.as_slice()).await;
+                    }
+                }
+                Err(e) => error!("failed to handle request from {:?}: {}", path, e),
             }
+        }
+    })
+}
 
-            let uds_message = UdsMessage::decode(&buf[..size]).unwrap();
-            match uds_message {
-                UdsMessage::Public(message) => {
-                    let user_input: UserInput = MessageRequest::into(message);
-                    debug!("user_input: {:?}", user_input);
+async fn handle_uds_datagram(
+    datagram: &[u8],
+    path: &Path,
+    uds_tx: &Sender<UserInput>,
+    server_tx: &Arc<UnixDatagram>,
+    events: &EventSender,
+) -> Result<(), UdsHandlerError> {
+    let uds_message = UdsMessage::decode(datagram).map_err(UdsHandlerError::DecodeFailed)?;
+    match uds_message {
+        UdsMessage::Public(message) => {
+            let user_input = UserInput {
+                input: message.into(),
+                source: InputSource::UnixDomainSocket(path.to_path_buf()),
+            };
+            debug!("user_input: {:?}", user_input);
 
-                    // Line 296
-                    if let Err(e) = uds_tx.send(user_input).await {
-                        eprintln!("Error sending user input: {}", e);
-                        return Err(e.into());
-                    }
+            uds_tx
+                .send(user_input)
+                .await
+                .map_err(|_| UdsHandlerError::ChannelClosed)?;
+        }
+        UdsMessage::Internal(message) => {
+            debug!("internal_message ok, {:?}", message);
+            match message {
+                ipc::internal::Message::Ping => {
+                    let pong = ipc::internal::Message::Pong
+                        .encode()
+                        .map_err(UdsHandlerError::EncodeFailed)?;
+                    ipc::send_to(server_tx, path.to_path_buf(), pong.as_slice())
+                        .await
+                        .map_err(UdsHandlerError::SocketError)?;
                 }
-                UdsMessage::Internal(message) => {
-                    debug!("internal_message ok, {:?}", message);
-                    match message {
-                        ipc::internal::Message::Ping => {
-                            ipc::send_to(
-                                &server_tx,
-                                addr.as_pathname().unwrap().to_path_buf(),
-                                ipc::internal::Message::Pong.encode().unwrap().as_slice(),
-                            )
-                            .await;
-                        }
-                        ipc::internal::Message::Pong => {}
-                    }
+                ipc::internal::Message::Pong => {}
+                ipc::internal::Message::Subscribe => {
+                    let _subscriber_handle = spawn_event_subscriber(
+                        server_tx.clone(),
+                        path.to_path_buf(),
+                        events.subscribe(),
+                    );
                 }
             }
         }
+    }
+
+    Ok(())
+}
+
+fn spawn_event_subscriber(
+    server_tx: Arc<UnixDatagram>,
+    target: PathBuf,
+    mut rx: broadcast::Receiver<Event>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        debug!("subscriber ({:?}) registered", target);
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(count)) => {
+                    debug!("subscriber ({:?}) lagged, {} events dropped", target, count);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            let encoded = match MessageResponse::new(vec![event.to_json()]).encode() {
+                Ok(encoded) => encoded,
+                Err(e) => {
+                    debug!("Error encoding event: {}", e);
+                    continue;
+                }
+            };
+
+            // the watch client is gone, clean up its stale socket file
+            if let Err(e) = ipc::send_to(&server_tx, target.clone(), encoded.as_slice()).await {
+                debug!("subscriber ({:?}) unreachable: {}", target, e);
+                let _ = fs::remove_file(&target);
+                break;
+            }
+        }
+        debug!("subscriber ({:?}) unregistered", target);
     })
 }
@@ -170,30 +170,34 @@ impl Tabled for Notification {
 
 pub fn get_new_notification_sled(
     matches: &ArgMatches,
-    created_at: DateTime<Utc>,
+    start_at: DateTime<Utc>,
     configuration: Arc<Configuration>,
 ) -> Result<NotificationSled, NotificationError> {
-    let (work_time, break_time, description) =
+    let (work_seconds, break_seconds, description, preset) =
         util::parse_work_and_break_time(matches, Some(&configuration))
             .map_err(NotificationError::NewNotification)?;
 
     // should never panic on unwrap as parse_work_and_break_time already handles it
-    let work_time = work_time.unwrap();
-    let break_time = break_time.unwrap();
+    let work_seconds = work_seconds.unwrap();
+    let break_seconds = break_seconds.unwrap();
     let description = description.unwrap();
 
-    debug!("work_time: {}", work_time);
-    debug!("break_time: {}", break_time);
+    debug!("work_seconds: {}", work_seconds);
+    debug!("break_seconds: {}", break_seconds);
     debug!("description: {}", description);
 
-    if work_time == 0 && break_time == 0 {
+    if work_seconds == 0 && break_seconds == 0 {
         return Err(NotificationError::EmptyTimeValues);
     }
 
-    Ok(NotificationSled::new(
-        description,
-        work_time,
-        break_time,
-        created_at,
-    ))
+    let (tags, project) = util::parse_labels(matches);
+
+    let mut notification =
+        NotificationSled::new(description, work_seconds, break_seconds, start_at);
+    notification.preset = preset;
+    notification.project = project;
+    notification.tags = tags;
+    notification.task = matches.get_one::<u64>("task").copied();
+
+    Ok(notification)
 }
@@ -10,6 +10,7 @@ use std::sync::Arc;
 
 use crate::configuration::{Configuration, SLACK_API_URL};
 use crate::error::{NotificationError, NotifyResult};
+use crate::goal::GoalProgress;
 use crate::report;
 
 #[cfg(target_os = "macos")]
@@ -35,7 +36,7 @@ fn notify_terminal_notifier(message: &'static str) {
 
 /// notify_slack send notification to slack
 /// it uses slack notification if configuration specified
-async fn notify_slack(message: &'static str, configuration: &Arc<Configuration>) -> NotifyResult {
+async fn notify_slack(message: &str, configuration: &Arc<Configuration>) -> NotifyResult {
     let token = configuration.get_slack_token();
     let channel = configuration.get_slack_channel();
 
@@ -69,7 +70,7 @@ async fn notify_slack(message: &'static str, configuration: &Arc<Configuration>)
 
 /// notify_discord send notification to discord
 /// use discord webhook notification if configuration specified
-async fn notify_discord(message: &'static str, configuration: &Arc<Configuration>) -> NotifyResult {
+async fn notify_discord(message: &str, configuration: &Arc<Configuration>) -> NotifyResult {
     let webhook_url = match configuration.get_discord_webhook_url() {
         Some(url) => url,
         None => {
@@ -96,7 +97,7 @@ async fn notify_discord(message: &'static str, configuration: &Arc<Configuration
 use std::process::Command;
 /// notify_desktop send notification to desktop.
 /// use notify-rust library for desktop notification
-async fn notify_desktop(summary_message: &'static str, body_message: &'static str) -> NotifyResult {
+async fn notify_desktop(summary_message: &str, body_message: &str) -> NotifyResult {
     let mut notification = NR_Notification::new();
     let notification = notification
         .summary(summary_message)
@@ -111,9 +112,9 @@ async fn notify_desktop(summary_message: &'static str, body_message: &'static st
 
     // Stop mpd from playing
     debug!("Running pomodoro_notify script");
-    let _ = Command::new("/home/sinh/.config/sinh-x-local/pomodoro_notify")
-        .output()
-        .expect("Failed to run fish script");
+    if let Err(e) = Command::new("/home/sinh/.config/sinh-x-local/pomodoro_notify").output() {
+        debug!("failed to run pomodoro_notify script: {}", e);
+    }
 
     notification
         .show()
@@ -121,14 +122,28 @@ async fn notify_desktop(summary_message: &'static str, body_message: &'static st
         .map_err(NotificationError::Desktop)
 }
 
-pub async fn notify_work(configuration: &Arc<Configuration>) -> Result<String, NotificationError> {
+// with_labels appends the project and tags of the notification to a message
+fn with_labels(message: &str, labels: Option<&str>) -> String {
+    match labels {
+        Some(labels) => format!("{} ({})", message, labels),
+        None => message.to_string(),
+    }
+}
+
+pub async fn notify_work(
+    configuration: &Arc<Configuration>,
+    labels: Option<&str>,
+) -> Result<String, NotificationError> {
+    let message = with_labels("work done. Take a rest!", labels);
+    let desktop_message = with_labels("Work time finished.\nNow take a rest!", labels);
+
     // TODO(young): Handle this also as async later
     #[cfg(target_os = "macos")]
     notify_terminal_notifier("work done. Take a rest!");
 
-    let desktop_fut = notify_desktop("Work time done!", "Work time finished.\nNow take a rest!");
-    let slack_fut = notify_slack("work done. Take a rest!", configuration);
-    let discord_fut = notify_discord("work done. Take a rest!", configuration);
+    let desktop_fut = notify_desktop("Work time done!", &desktop_message);
+    let slack_fut = notify_slack(&message, configuration);
+    let discord_fut = notify_discord(&message, configuration);
 
     let (desktop_result, slack_result, discord_result) = join!(desktop_fut, slack_fut, discord_fut);
 
@@ -139,16 +154,77 @@ pub async fn notify_work(configuration: &Arc<Configuration>) -> Result<String, N
     ))
 }
 
-pub async fn notify_break(configuration: &Arc<Configuration>) -> Result<String, NotificationError> {
+pub async fn notify_break(
+    configuration: &Arc<Configuration>,
+    labels: Option<&str>,
+) -> Result<String, NotificationError> {
+    let message = with_labels("break done. Get back to work", labels);
+    let desktop_message = with_labels("Break time finished.\n Now back to work!", labels);
+
     #[cfg(target_os = "macos")]
     notify_terminal_notifier("break done. Get back to work");
 
-    let desktop_fut = notify_desktop(
-        "Break time done!",
-        "Break time finished.\n Now back to work!",
-    );
-    let slack_fut = notify_slack("break done. Get back to work", configuration);
-    let discord_fut = notify_discord("break done. Get back to work", configuration);
+    let desktop_fut = notify_desktop("Break time done!", &desktop_message);
+    let slack_fut = notify_slack(&message, configuration);
+    let discord_fut = notify_discord(&message, configuration);
+
+    let (desktop_result, slack_result, discord_result) = join!(desktop_fut, slack_fut, discord_fut);
+
+    Ok(report::generate_notify_report(
+        desktop_result,
+        slack_result,
+        discord_result,
+    ))
+}
+
+/// notify_missed sends the summary of notifications which ended while the server was down
+pub async fn notify_missed(
+    configuration: &Arc<Configuration>,
+    message: &str,
+) -> Result<String, NotificationError> {
+    let desktop_fut = notify_desktop("Pomodoros ended while pomodoro was stopped", message);
+    let slack_fut = notify_slack(message, configuration);
+    let discord_fut = notify_discord(message, configuration);
+
+    let (desktop_result, slack_result, discord_result) = join!(desktop_fut, slack_fut, discord_fut);
+
+    Ok(report::generate_notify_report(
+        desktop_result,
+        slack_result,
+        discord_result,
+    ))
+}
+
+/// notify_goal_reached celebrates the pomodoro which reaches the daily goal
+pub async fn notify_goal_reached(
+    configuration: &Arc<Configuration>,
+    progress: &GoalProgress,
+) -> Result<String, NotificationError> {
+    let message = format!("daily goal reached with {} today. Well done!", progress);
+
+    let desktop_fut = notify_desktop("Daily goal reached!", &message);
+    let slack_fut = notify_slack(&message, configuration);
+    let discord_fut = notify_discord(&message, configuration);
+
+    let (desktop_result, slack_result, discord_result) = join!(desktop_fut, slack_fut, discord_fut);
+
+    Ok(report::generate_notify_report(
+        desktop_result,
+        slack_result,
+        discord_result,
+    ))
+}
+
+/// notify_goal_missed reminds of a daily goal not reached at `daily_goal.reminder`
+pub async fn notify_goal_missed(
+    configuration: &Arc<Configuration>,
+    progress: &GoalProgress,
+) -> Result<String, NotificationError> {
+    let message = format!("daily goal not reached yet, {} today", progress);
+
+    let desktop_fut = notify_desktop("Daily goal reminder", &message);
+    let slack_fut = notify_slack(&message, configuration);
+    let discord_fut = notify_discord(&message, configuration);
 
     let (desktop_result, slack_result, discord_result) = join!(desktop_fut, slack_fut, discord_fut);
 
@@ -1,6 +1,7 @@
 use colored::{ColoredString, Colorize};
 use tabled::{Style, Table, Tabled};
 
+use crate::credential::Credential;
 use crate::error::ConfigurationError;
 use crate::error::NotifyResult;
 use crate::Configuration;
@@ -21,6 +22,21 @@ impl Report {
         }
     }
 
+    // note tells where an available value comes from
+    pub fn update_note(mut self, note: String) -> Self {
+        self.reason = note.as_str().normal();
+
+        self
+    }
+
+    // warning keeps the configuration usable, shown in yellow
+    pub fn update_warning(mut self, e: &ConfigurationError) -> Self {
+        self.ok = self.ok.yellow();
+        self.reason = e.to_string().yellow();
+
+        self
+    }
+
     // TODO(young): e should be string or ConfigurationError?
     pub fn update_reason(mut self, e: &dyn std::error::Error) -> Self {
         let mut vec = vec![format!("{}", e)];
@@ -49,18 +65,23 @@ pub fn generate_configuration_report(
             .update_reason(&ConfigurationError::SlackConfigNotFound),
     };
 
-    let slack_token_message = match config.get_slack_token() {
-        Some(_) => Report::new("O", "slack_token"),
-        None => {
-            Report::new("X", "slack_token").update_reason(&ConfigurationError::SlackConfigNotFound)
-        }
-    };
+    let slack_token_message = get_credential_report(
+        "slack_token",
+        config.get_slack_token_credential(),
+        ConfigurationError::SlackConfigNotFound,
+    );
 
-    let discord_webhook_url_message = match config.get_discord_webhook_url() {
-        Some(_) => Report::new("O", "discord_webhook_url"),
-        None => Report::new("X", "discord_webhook_url")
-            .update_reason(&ConfigurationError::DiscordConfigNotFound),
-    };
+    let discord_webhook_url_message = get_credential_report(
+        "discord_webhook_url",
+        config.get_discord_webhook_url_credential(),
+        ConfigurationError::DiscordConfigNotFound,
+    );
+
+    let http_token_message = get_credential_report(
+        "http_token",
+        config.get_http_token_credential(),
+        ConfigurationError::HttpConfigNotFound,
+    );
 
     let work_time_default_value_message = match config.get_work_time() {
         Some(_) => Report::new("O", "default_work_time"),
@@ -74,16 +95,49 @@ pub fn generate_configuration_report(
             .update_reason(&ConfigurationError::UnspecifiedBreakTime),
     };
 
-    Table::new(vec![
+    let presets = config
+        .get_presets()
+        .into_iter()
+        .map(|(name, preset)| format!("{} ({}/{})", name, preset.work, preset.r#break))
+        .collect::<Vec<String>>();
+    let presets_message = match presets.is_empty() {
+        true => Report::new("-", "presets").update_note(String::from("none")),
+        false => Report::new("O", "presets").update_note(presets.join(", ")),
+    };
+
+    let warning_messages = config
+        .get_warnings()
+        .into_iter()
+        .map(|warning| Report::new("!", "config warning").update_warning(warning));
+
+    let mut reports = vec![
         config_err_message,
         slack_channel_message,
         slack_token_message,
         discord_webhook_url_message,
+        http_token_message,
         work_time_default_value_message,
         break_time_default_value_message,
-    ])
-    .with(Style::modern())
-    .to_string()
+        presets_message,
+    ];
+    reports.extend(warning_messages);
+
+    Table::new(reports).with(Style::modern()).to_string()
+}
+
+// get_credential_report shows where the secret comes from, never the secret itself
+fn get_credential_report(
+    desc: &'static str,
+    credential: Option<&Credential>,
+    not_found: ConfigurationError,
+) -> Report {
+    match credential {
+        Some(credential) => match credential.get_error() {
+            Some(e) => Report::new("X", desc).update_reason(e),
+            None => Report::new("O", desc).update_note(format!("from {}", credential.get_source())),
+        },
+        None => Report::new("X", desc).update_reason(&not_found),
+    }
 }
 
 pub fn generate_notify_report(
@@ -3,39 +3,131 @@ use serde::{Deserialize, Serialize};
 use serde_json::{from_slice, to_vec};
 use sled::Db;
 use std::borrow::Cow;
+use std::fmt;
 use std::path::Path;
 use tabled::Tabled;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::error::StoreError;
+use crate::task::Task;
+
+// server state lives in its own tree, the default tree only holds notifications
+const SERVER_STATE_TREE: &str = "server_state";
+// tasks are keyed by their id in big endian, so the last key holds the highest id
+const TASKS_TREE: &str = "tasks";
+const LAST_STOPPED_AT_KEY: &str = "last_stopped_at";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotificationSled {
     pub id: Uuid,
     pub description: String,
-    pub work_time: u16,
-    pub break_time: u16,
+    #[serde(default)]
+    pub work_seconds: u32,
+    #[serde(default)]
+    pub break_seconds: u32,
+    // durations of records written before seconds were stored, in minutes
+    #[serde(default, rename = "work_time", skip_serializing)]
+    legacy_work_minutes: Option<u16>,
+    #[serde(default, rename = "break_time", skip_serializing)]
+    legacy_break_minutes: Option<u16>,
     pub created_at: DateTime<Utc>,
+    // records written before the start time was stored derive it from the expiry times
+    #[serde(default)]
+    pub start_at: Option<DateTime<Utc>>,
     pub work_expired_at: DateTime<Utc>,
     pub break_expired_at: DateTime<Utc>,
+    // set on start up for notifications which ended while the server was down
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<NotificationOutcome>,
+    // the preset the durations were taken from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // the id of the task the pomodoro is spent on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interruptions: Vec<Interruption>,
+    // written with `note` and `rate` once the work time is over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // focus rating from 1 to 5
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    // set while the timers are stopped with `pause`, the expiry times move on `resume`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InterruptionKind {
+    // the worker interrupted themselves
+    Internal,
+    // someone or something else did
+    External,
+}
+
+/// An interruption recorded with `interrupt` during the work phase of a pomodoro.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Interruption {
+    pub at: DateTime<Utc>,
+    pub kind: InterruptionKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationOutcome {
+    // was running when the server stopped, ended without notification
+    CompletedUnnotified,
+    // was queued to start after the server stopped
+    Missed,
+}
+
+impl fmt::Display for NotificationOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationOutcome::CompletedUnnotified => write!(f, "completed, not notified"),
+            NotificationOutcome::Missed => write!(f, "missed"),
+        }
+    }
 }
 
 impl<'a> NotificationSled {
     pub fn new(
         description: String,
-        work_time: u16,
-        break_time: u16,
-        created_at: DateTime<Utc>,
+        work_seconds: u32,
+        break_seconds: u32,
+        start_at: DateTime<Utc>,
     ) -> Self {
         let id = Uuid::new_v4();
-        let work_expired_at = created_at + Duration::minutes(work_time as i64);
-        let break_expired_at = work_expired_at + Duration::minutes(break_time as i64);
+        let work_expired_at = start_at + Duration::seconds(work_seconds as i64);
+        let break_expired_at = work_expired_at + Duration::seconds(break_seconds as i64);
         Self {
             id,
             description,
-            work_time,
-            break_time,
-            created_at,
+            work_seconds,
+            break_seconds,
+            legacy_work_minutes: None,
+            legacy_break_minutes: None,
+            created_at: Utc::now(),
+            start_at: Some(start_at),
             work_expired_at,
             break_expired_at,
+            outcome: None,
+            preset: None,
+            project: None,
+            tags: Vec::new(),
+            task: None,
+            interruptions: Vec::new(),
+            note: None,
+            rating: None,
+            paused_at: None,
         }
     }
 
@@ -46,12 +138,102 @@ impl<'a> NotificationSled {
     }
 
     pub fn get_start_at(&self) -> DateTime<Utc> {
+        if let Some(start_at) = self.start_at {
+            return start_at;
+        }
+
         let last_expired_at = self.work_expired_at.max(self.break_expired_at);
-        let duration = Duration::minutes((self.work_time + self.break_time) as i64);
+        let duration = Duration::seconds(self.work_seconds as i64 + self.break_seconds as i64);
 
         last_expired_at - duration
     }
 
+    // migrate_durations converts the durations stored in minutes, returns whether it did
+    fn migrate_durations(&mut self) -> bool {
+        let (work, r#break) = (
+            self.legacy_work_minutes.take(),
+            self.legacy_break_minutes.take(),
+        );
+        if let Some(minutes) = work {
+            self.work_seconds = minutes as u32 * 60;
+        }
+        if let Some(minutes) = r#break {
+            self.break_seconds = minutes as u32 * 60;
+        }
+
+        work.is_some() || r#break.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    // get_clock returns the time the phases are measured at, it stands still while paused
+    pub fn get_clock(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.paused_at.map_or(now, |paused_at| paused_at.min(now))
+    }
+
+    // resume moves the start and expiry times by the paused duration, returns whether it was paused
+    pub fn resume(&mut self, now: DateTime<Utc>) -> bool {
+        let Some(paused_at) = self.paused_at else {
+            return false;
+        };
+        self.postpone((now - paused_at).max(Duration::zero()));
+        self.paused_at = None;
+
+        true
+    }
+
+    // postpone moves the start and expiry times later by `by`
+    pub fn postpone(&mut self, by: Duration) {
+        self.start_at = Some(self.get_start_at() + by);
+        self.work_expired_at += by;
+        self.break_expired_at += by;
+    }
+
+    // is_registered tells whether the notification is paused or still has a phase to run
+    pub fn is_registered(&self, now: DateTime<Utc>) -> bool {
+        self.is_paused() || self.work_expired_at > now || self.break_expired_at > now
+    }
+
+    pub fn is_working(&self, now: DateTime<Utc>) -> bool {
+        let now = self.get_clock(now);
+        self.work_seconds > 0 && self.get_start_at() <= now && self.work_expired_at > now
+    }
+
+    // is_work_done tells whether the work time is over, missed notifications never started
+    pub fn is_work_done(&self, now: DateTime<Utc>) -> bool {
+        let now = self.get_clock(now);
+        self.work_seconds > 0
+            && self.work_expired_at <= now
+            && self.outcome != Some(NotificationOutcome::Missed)
+    }
+
+    pub fn count_interruptions(&self, kind: InterruptionKind) -> usize {
+        self.interruptions.iter().filter(|i| i.kind == kind).count()
+    }
+
+    // has_labels tells whether the notification belongs to the project and carries the tag,
+    // a filter not given matches every notification
+    pub fn has_labels(&self, tag: Option<&str>, project: Option<&str>) -> bool {
+        let has_tag = tag.map_or(true, |tag| self.tags.iter().any(|t| t == tag));
+        let has_project = project.map_or(true, |project| self.project.as_deref() == Some(project));
+
+        has_tag && has_project
+    }
+
+    // get_labels returns the project and tags like `billing #client #review`
+    pub fn get_labels(&self) -> Option<String> {
+        let labels = self
+            .project
+            .iter()
+            .cloned()
+            .chain(self.tags.iter().map(|tag| format!("#{}", tag)))
+            .collect::<Vec<String>>();
+
+        (!labels.is_empty()).then(|| labels.join(" "))
+    }
+
     pub fn get_work_time(&self) -> u64 {
         debug!("get_next_notify: id {}", self.id);
         if self.work_expired_at <= Utc::now() {
@@ -75,9 +257,6 @@ impl<'a> NotificationSled {
         }
     }
 
-
-
-
     #[allow(dead_code)]
     //TODO: review this function
     pub fn get_values(
@@ -85,8 +264,8 @@ impl<'a> NotificationSled {
     ) -> (
         Uuid,
         &'a str,
-        u16,
-        u16,
+        u32,
+        u32,
         DateTime<Utc>,
         DateTime<Utc>,
         DateTime<Utc>,
@@ -94,19 +273,37 @@ impl<'a> NotificationSled {
         (
             self.id,
             self.description.as_str(),
-            self.work_time,
-            self.break_time,
+            self.work_seconds,
+            self.break_seconds,
             self.created_at,
             self.work_expired_at,
             self.break_expired_at,
         )
     }
 
+    // get_outcome_after_downtime classifies a notification which ended between the server stop and now
+    pub fn get_outcome_after_downtime(
+        &self,
+        stopped_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<NotificationOutcome> {
+        let ended_at = self.work_expired_at.max(self.break_expired_at);
+        if self.outcome.is_some() || self.is_paused() || ended_at <= stopped_at || ended_at > now {
+            return None;
+        }
+
+        if self.get_start_at() >= stopped_at {
+            Some(NotificationOutcome::Missed)
+        } else {
+            Some(NotificationOutcome::CompletedUnnotified)
+        }
+    }
+
     pub fn get_work_percentage(&self, current_time: DateTime<Utc>) -> String {
+        let current_time = self.get_clock(current_time);
         // Check if the timer has started and contains some work_time
         if self.work_expired_at > current_time && self.get_start_at() < current_time {
-            // do the calculation in seconds for better % accuracy
-            let work_time_seconds: i64 = self.work_time as i64 * 60;
+            let work_time_seconds = self.work_seconds as i64;
 
             let completed_time =
                 work_time_seconds - (self.work_expired_at - current_time).num_seconds();
@@ -123,14 +320,14 @@ impl<'a> NotificationSled {
 }
 
 impl Tabled for NotificationSled {
-    const LENGTH: usize = 8;
+    const LENGTH: usize = 14;
 
     fn fields(&self) -> Vec<Cow<'_, str>> {
-        let utc = Utc::now();
+        let utc = self.get_clock(Utc::now());
 
         let id = self.id.to_string();
 
-        let work_remaining = if self.work_time > 0 {
+        let work_remaining = if self.work_seconds > 0 {
             let sec = (self.work_expired_at - utc).num_seconds();
 
             if sec > 0 {
@@ -145,7 +342,7 @@ impl Tabled for NotificationSled {
             String::from("N/A")
         };
 
-        let break_remaining = if self.break_time > 0 {
+        let break_remaining = if self.break_seconds > 0 {
             let sec = (self.break_expired_at - utc).num_seconds();
 
             if sec > 0 {
@@ -165,22 +362,55 @@ impl Tabled for NotificationSled {
             local_time.format("%F %T %z").to_string()
         };
 
-        let description = self.description.to_string();
+        let mut description = match self.outcome {
+            Some(outcome) => format!("{} ({})", self.description, outcome),
+            None => self.description.to_string(),
+        };
+        if self.is_paused() {
+            description.push_str(" (paused)");
+        }
+        if let Some(task) = self.task {
+            description.push_str(&format!(" [task {}]", task));
+        }
 
-        let work_expired_at = if self.work_time > 0 {
+        let work_expired_at = if self.work_seconds > 0 {
             let local_time: DateTime<Local> = self.work_expired_at.into();
             local_time.format("%F %T %z").to_string()
         } else {
             String::from("N/A")
         };
 
-        let break_expired_at = if self.break_time > 0 {
+        let break_expired_at = if self.break_seconds > 0 {
             let local_time: DateTime<Local> = self.break_expired_at.into();
             local_time.format("%F %T %z").to_string()
         } else {
             String::from("N/A")
         };
 
+        let preset = self.preset.clone().unwrap_or_else(|| String::from("-"));
+        let project = self.project.clone().unwrap_or_else(|| String::from("-"));
+        let tags = if self.tags.is_empty() {
+            String::from("-")
+        } else {
+            self.tags.join(", ")
+        };
+
+        let interruptions = if self.interruptions.is_empty() {
+            String::from("-")
+        } else {
+            format!(
+                "{}/{}",
+                self.count_interruptions(InterruptionKind::Internal),
+                self.count_interruptions(InterruptionKind::External)
+            )
+        };
+
+        let rating = self
+            .rating
+            .map(|rating| rating.to_string())
+            .unwrap_or_else(|| String::from("-"));
+        let note = self.note.clone().unwrap_or_else(|| String::from("-"));
+
         let work_percentage = self.get_work_percentage(utc);
 
         vec![
@@ -191,6 +421,12 @@ impl Tabled for NotificationSled {
             work_expired_at,
             break_expired_at,
             description,
+            preset,
+            project,
+            tags,
+            interruptions,
+            rating,
+            note,
             work_percentage,
         ]
         .into_iter()
@@ -207,6 +443,12 @@ impl Tabled for NotificationSled {
             "expired_at (work)",
             "expired_at (break)",
             "description",
+            "preset",
+            "project",
+            "tags",
+            "interruptions (int/ext)",
+            "focus",
+            "note",
             "percentage",
         ]
         .into_iter()
@@ -215,19 +457,50 @@ impl Tabled for NotificationSled {
     }
 }
 
+// the db is shared by its clones, like an Arc
+#[derive(Clone)]
 pub struct SledStore {
     db: Db,
 }
 
 impl SledStore {
-    pub fn new(path: &Path) -> Result<Self, sled::Error> {
-        let db = sled::open(path)?;
-        Ok(Self { db })
+    pub fn new(path: &Path) -> Result<Self, StoreError> {
+        let db = sled::open(path).map_err(|e| StoreError::OpenFailed(path.to_path_buf(), e))?;
+        let store = Self { db };
+        store.migrate_durations()?;
+
+        Ok(store)
+    }
+
+    // records written with durations in minutes are rewritten in seconds, once
+    fn migrate_durations(&self) -> Result<usize, StoreError> {
+        let mut migrated = 0;
+        for item in self.db.iter() {
+            let (key, value) = item?;
+            // corrupted records are reported when they are read
+            let mut notification: NotificationSled = match from_slice(&value) {
+                Ok(notification) => notification,
+                Err(_) => continue,
+            };
+
+            if notification.migrate_durations() {
+                let value = to_vec(&notification).map_err(StoreError::EncodeFailed)?;
+                self.db.insert(key, value)?;
+                migrated += 1;
+            }
+        }
+
+        if migrated > 0 {
+            self.db.flush()?;
+            info!("stored durations of {} notifications in seconds", migrated);
+        }
+
+        Ok(migrated)
     }
 
-    pub fn insert(&self, uuid: Uuid, notification: &NotificationSled) -> Result<(), sled::Error> {
+    pub fn insert(&self, uuid: Uuid, notification: &NotificationSled) -> Result<(), StoreError> {
         let key = uuid.as_bytes();
-        let value = to_vec(notification).unwrap();
+        let value = to_vec(notification).map_err(StoreError::EncodeFailed)?;
         self.db.insert(key, value)?;
         self.db.flush().map_err(|e| e)?;
         Ok(())
@@ -235,11 +508,11 @@ impl SledStore {
 
     #[allow(dead_code)]
     //TODO: review this function
-    pub fn get(&self, uuid: Uuid) -> Result<Option<NotificationSled>, sled::Error> {
+    pub fn get(&self, uuid: Uuid) -> Result<Option<NotificationSled>, StoreError> {
         let key = uuid.as_bytes();
         match self.db.get(key)? {
             Some(value) => {
-                let notification: NotificationSled = from_slice(&value).unwrap();
+                let notification = decode_notification(key, &value)?;
                 Ok(Some(notification))
             }
             None => Ok(None),
@@ -248,31 +521,123 @@ impl SledStore {
 
     #[allow(dead_code)]
     //TODO: review this function
-    pub fn delete(&self, uuid: Uuid) -> Result<(), sled::Error> {
+    pub fn delete(&self, uuid: Uuid) -> Result<(), StoreError> {
         let key = uuid.as_bytes();
         self.db.remove(key)?;
         self.db.flush().map_err(|e| e)?;
         Ok(())
     }
 
-    pub fn create_notification(
-        &self,
-        notification: &NotificationSled,
-    ) -> Result<Uuid, sled::Error> {
+    pub fn flush(&self) -> Result<usize, StoreError> {
+        Ok(self.db.flush()?)
+    }
+
+    pub fn record_server_stop(&self, stopped_at: DateTime<Utc>) -> Result<(), StoreError> {
+        let tree = self.db.open_tree(SERVER_STATE_TREE)?;
+        tree.insert(LAST_STOPPED_AT_KEY, stopped_at.to_rfc3339().as_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    // take_last_server_stop removes the stop time, so a crash of this run is not mistaken for a clean stop
+    pub fn take_last_server_stop(&self) -> Result<Option<DateTime<Utc>>, StoreError> {
+        let tree = self.db.open_tree(SERVER_STATE_TREE)?;
+        let stopped_at = tree.remove(LAST_STOPPED_AT_KEY)?.and_then(|value| {
+            std::str::from_utf8(&value)
+                .ok()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|t| t.with_timezone(&Utc))
+        });
+        self.db.flush()?;
+        Ok(stopped_at)
+    }
+
+    // reconcile marks notifications which ended while the server was down and returns them
+    pub fn reconcile(&self, now: DateTime<Utc>) -> Result<Vec<NotificationSled>, StoreError> {
+        let stopped_at = match self.take_last_server_stop()? {
+            Some(stopped_at) => stopped_at,
+            None => {
+                debug!("no recorded server stop, skip reconciliation");
+                return Ok(Vec::new());
+            }
+        };
+
+        let mut reconciled = Vec::new();
+        for mut notification in self.list_all_notifications()? {
+            if let Some(outcome) = notification.get_outcome_after_downtime(stopped_at, now) {
+                notification.outcome = Some(outcome);
+                self.insert(notification.id, &notification)?;
+                reconciled.push(notification);
+            }
+        }
+
+        Ok(reconciled)
+    }
+
+    pub fn create_notification(&self, notification: &NotificationSled) -> Result<Uuid, StoreError> {
         let uuid = notification.id;
         self.insert(uuid, &notification)?;
         Ok(uuid)
     }
 
-    pub fn list_notifications(&self) -> Result<Vec<NotificationSled>, sled::Error> {
+    pub fn list_notifications(&self) -> Result<Vec<NotificationSled>, StoreError> {
+        let now = Utc::now();
+        let mut notifications = self.list_all_notifications()?;
+        notifications.retain(|notification| notification.is_registered(now));
+
+        Ok(notifications)
+    }
+
+    // postpone_queued moves the notifications waiting to start at or after `after` later by `by`,
+    // so the ones queued behind a resumed pomodoro don't overlap it. Returns the moved ones
+    pub fn postpone_queued(
+        &self,
+        after: DateTime<Utc>,
+        by: Duration,
+    ) -> Result<Vec<NotificationSled>, StoreError> {
+        let mut postponed = Vec::new();
+        for mut notification in self.list_notifications()? {
+            if notification.is_paused() || notification.get_start_at() < after {
+                continue;
+            }
+
+            notification.postpone(by);
+            self.insert(notification.id, &notification)?;
+            postponed.push(notification);
+        }
+
+        Ok(postponed)
+    }
+
+    // clear_history removes the archived notifications with the labels and returns how many,
+    // registered ones are left to `delete` so their timers are stopped
+    pub fn clear_history(
+        &self,
+        tag: Option<&str>,
+        project: Option<&str>,
+    ) -> Result<usize, StoreError> {
+        let now = Utc::now();
+        let mut cleared = 0;
+        for notification in self.list_all_notifications()? {
+            if !notification.is_registered(now) && notification.has_labels(tag, project) {
+                self.db.remove(notification.id.as_bytes())?;
+                cleared += 1;
+            }
+        }
+        self.db.flush()?;
+
+        Ok(cleared)
+    }
+
+    // list_all_notifications skips the records which fail to decode, so one corrupted record
+    // doesn't hide the others. Failing to read the tree is still an error
+    pub fn list_all_notifications(&self) -> Result<Vec<NotificationSled>, StoreError> {
         let mut notifications = Vec::new();
         for item in self.db.iter() {
-            let (_, value) = item?;
-            let notification: NotificationSled = from_slice(&value).unwrap();
-            if notification.work_expired_at > Utc::now()
-                || notification.break_expired_at > Utc::now()
-            {
-                notifications.push(notification);
+            let (key, value) = item?;
+            match decode_notification(&key, &value) {
+                Ok(notification) => notifications.push(notification),
+                Err(e) => warn!("skip corrupted notification: {}", e),
             }
         }
 
@@ -280,19 +645,83 @@ impl SledStore {
         Ok(notifications)
     }
 
-    pub fn list_all_notifications(&self) -> Result<Vec<NotificationSled>, sled::Error> {
-        let mut notifications = Vec::new();
-        for item in self.db.iter() {
-            let (_, value) = item?;
-            let notification: NotificationSled = from_slice(&value).unwrap();
-            notifications.push(notification);
+    // record_interruption adds the interruption to the pomodoro in its work phase,
+    // the one finishing its work first when several overlap
+    pub fn record_interruption(
+        &self,
+        interruption: Interruption,
+    ) -> Result<Option<Uuid>, StoreError> {
+        let now = interruption.at;
+        let working = self
+            .list_notifications()?
+            .into_iter()
+            .filter(|n| n.is_working(now))
+            .min_by_key(|n| n.work_expired_at);
+
+        match working {
+            Some(mut notification) => {
+                notification.interruptions.push(interruption);
+                self.insert(notification.id, &notification)?;
+                Ok(Some(notification.id))
+            }
+            None => Ok(None),
         }
+    }
+
+    // find_notifications returns the notifications whose id starts with the prefix
+    pub fn find_notifications(&self, prefix: &str) -> Result<Vec<NotificationSled>, StoreError> {
+        let prefix = prefix.to_lowercase();
+        let notifications = self
+            .list_all_notifications()?
+            .into_iter()
+            .filter(|n| n.id.to_string().starts_with(&prefix))
+            .collect();
 
-        notifications.sort_by(|a, b| b.work_expired_at.cmp(&a.work_expired_at));
         Ok(notifications)
     }
 
-    pub fn get_time_for_queue_notification(&self) -> Result<DateTime<Utc>, sled::Error> {
+    pub fn add_task(&self, title: String, estimate: Option<u16>) -> Result<Task, StoreError> {
+        let tree = self.db.open_tree(TASKS_TREE)?;
+        let id = match tree.last()? {
+            Some((key, _)) => decode_task_id(&key) + 1,
+            None => 1,
+        };
+
+        let task = Task::new(id, title, estimate);
+        self.update_task(&task)?;
+        Ok(task)
+    }
+
+    pub fn update_task(&self, task: &Task) -> Result<(), StoreError> {
+        let tree = self.db.open_tree(TASKS_TREE)?;
+        let value = to_vec(task).map_err(StoreError::EncodeFailed)?;
+        tree.insert(task.id.to_be_bytes(), value)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    pub fn get_task(&self, id: u64) -> Result<Option<Task>, StoreError> {
+        let tree = self.db.open_tree(TASKS_TREE)?;
+        let key = id.to_be_bytes();
+        match tree.get(key)? {
+            Some(value) => Ok(Some(decode_task(&key, &value)?)),
+            None => Ok(None),
+        }
+    }
+
+    // list_tasks returns the tasks ordered by id
+    pub fn list_tasks(&self) -> Result<Vec<Task>, StoreError> {
+        let tree = self.db.open_tree(TASKS_TREE)?;
+        let mut tasks = Vec::new();
+        for item in tree.iter() {
+            let (key, value) = item?;
+            tasks.push(decode_task(&key, &value)?);
+        }
+
+        Ok(tasks)
+    }
+
+    pub fn get_time_for_queue_notification(&self) -> Result<DateTime<Utc>, StoreError> {
         let created_at = match self.list_notifications() {
             Ok(notifications) => {
                 if notifications.len() > 0 {
@@ -307,3 +736,424 @@ impl SledStore {
         Ok(created_at)
     }
 }
+
+// decode_notification reads a stored value, keys are uuids unless the database was tampered with
+fn decode_notification(key: &[u8], value: &[u8]) -> Result<NotificationSled, StoreError> {
+    let mut notification: NotificationSled = from_slice(value).map_err(|e| {
+        let key = Uuid::from_slice(key)
+            .map(|uuid| uuid.to_string())
+            .unwrap_or_else(|_| String::from_utf8_lossy(key).to_string());
+        StoreError::DecodeFailed(key, e)
+    })?;
+    notification.migrate_durations();
+
+    Ok(notification)
+}
+
+fn decode_task_id(key: &[u8]) -> u64 {
+    key.try_into().map(u64::from_be_bytes).unwrap_or_default()
+}
+
+fn decode_task(key: &[u8], value: &[u8]) -> Result<Task, StoreError> {
+    from_slice(value)
+        .map_err(|e| StoreError::DecodeFailed(format!("task {}", decode_task_id(key)), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use proptest::prelude::*;
+
+    use super::{
+        decode_notification, Interruption, InterruptionKind, NotificationOutcome, NotificationSled,
+        SledStore,
+    };
+    use crate::error::StoreError;
+
+    fn get_temporary_store() -> SledStore {
+        SledStore {
+            db: sled::Config::new().temporary(true).open().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_get_outcome_after_downtime() {
+        let now = Utc::now();
+        let stopped_at = now - Duration::minutes(60);
+
+        // running when the server stopped
+        let running = NotificationSled::new(
+            String::from("a"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(70),
+        );
+        assert_eq!(
+            running.get_outcome_after_downtime(stopped_at, now),
+            Some(NotificationOutcome::CompletedUnnotified)
+        );
+
+        // queued to start while the server was down
+        let queued = NotificationSled::new(
+            String::from("b"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(40),
+        );
+        assert_eq!(
+            queued.get_outcome_after_downtime(stopped_at, now),
+            Some(NotificationOutcome::Missed)
+        );
+
+        // ended before the stop, or still running
+        let ended = NotificationSled::new(
+            String::from("c"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(120),
+        );
+        assert_eq!(ended.get_outcome_after_downtime(stopped_at, now), None);
+        let active = NotificationSled::new(
+            String::from("d"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(10),
+        );
+        assert_eq!(active.get_outcome_after_downtime(stopped_at, now), None);
+    }
+
+    #[test]
+    fn test_corrupted_notification() {
+        let store = get_temporary_store();
+        let notification = NotificationSled::new(String::from("a"), 25 * 60, 5 * 60, Utc::now());
+        store.create_notification(&notification).unwrap();
+        assert_eq!(store.list_all_notifications().unwrap().len(), 1);
+
+        let id = uuid::Uuid::new_v4();
+        store
+            .db
+            .insert(id.as_bytes(), &b"not a notification"[..])
+            .unwrap();
+        // listing skips the corrupted record, reading it alone is an error
+        let notifications = store.list_all_notifications().unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].id, notification.id);
+        assert_eq!(store.list_notifications().unwrap().len(), 1);
+        match store.get(id) {
+            Err(StoreError::DecodeFailed(key, _)) => assert_eq!(key, id.to_string()),
+            other => panic!(
+                "expected decode error, got {:?}",
+                other.map(|n| n.is_some())
+            ),
+        }
+        assert!(store.get(notification.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_start_at() {
+        let start_at = Utc::now() + Duration::minutes(30);
+        let scheduled = NotificationSled::new(String::from("a"), 25 * 60, 5 * 60, start_at);
+        assert_eq!(scheduled.get_start_at(), start_at);
+        assert!(scheduled.created_at < start_at);
+
+        // stored before the start time was kept
+        let value = r#"{"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","description":"a","work_time":25,"break_time":5,"created_at":"2024-05-01T10:00:00Z","work_expired_at":"2024-05-01T10:25:00Z","break_expired_at":"2024-05-01T10:30:00Z"}"#;
+        let stored = decode_notification(b"key", value.as_bytes()).unwrap();
+        assert_eq!(stored.start_at, None);
+        assert_eq!(stored.get_start_at(), stored.created_at);
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let store = get_temporary_store();
+        let now = Utc::now();
+        let mut notification = NotificationSled::new(
+            String::from("a"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(10),
+        );
+        notification.paused_at = Some(now);
+        store.create_notification(&notification).unwrap();
+
+        // a paused pomodoro stays registered and in its work phase past its expiry times
+        let later = now + Duration::minutes(60);
+        assert!(notification.is_working(later));
+        assert!(!notification.is_work_done(later));
+        assert_eq!(notification.get_outcome_after_downtime(now, later), None);
+        assert_eq!(store.list_notifications().unwrap().len(), 1);
+
+        assert!(notification.resume(later));
+        assert!(!notification.is_paused());
+        assert_eq!(notification.get_start_at(), later - Duration::minutes(10));
+        assert_eq!(notification.work_expired_at, later + Duration::minutes(15));
+        assert_eq!(notification.break_expired_at, later + Duration::minutes(20));
+        assert!(!notification.resume(later));
+    }
+
+    #[test]
+    fn test_postpone_queued() {
+        let store = get_temporary_store();
+        let now = Utc::now();
+        let mut paused = NotificationSled::new(
+            String::from("a"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(10),
+        );
+        paused.paused_at = Some(now - Duration::minutes(5));
+        let queued =
+            NotificationSled::new(String::from("b"), 25 * 60, 5 * 60, paused.break_expired_at);
+        let scheduled_before = NotificationSled::new(
+            String::from("c"),
+            25 * 60,
+            5 * 60,
+            now + Duration::minutes(1),
+        );
+        for notification in [&paused, &queued, &scheduled_before] {
+            store.create_notification(notification).unwrap();
+        }
+
+        let postponed = store
+            .postpone_queued(paused.break_expired_at, Duration::minutes(5))
+            .unwrap();
+        assert_eq!(postponed.len(), 1);
+        assert_eq!(postponed[0].id, queued.id);
+
+        // the queued one still starts right after the resumed one
+        assert!(paused.resume(now));
+        let stored = store.get(queued.id).unwrap().unwrap();
+        assert_eq!(stored.get_start_at(), paused.break_expired_at);
+        assert_eq!(
+            stored.work_expired_at,
+            queued.work_expired_at + Duration::minutes(5)
+        );
+        assert_eq!(
+            store
+                .get(scheduled_before.id)
+                .unwrap()
+                .unwrap()
+                .get_start_at(),
+            scheduled_before.get_start_at()
+        );
+    }
+
+    #[test]
+    fn test_migrate_durations() {
+        let path = std::env::temp_dir().join(format!(
+            "pomodoro-sled-migration-test-{}",
+            std::process::id()
+        ));
+        let id = uuid::Uuid::new_v4();
+        {
+            let db = sled::open(&path).unwrap();
+            let value = format!(
+                r#"{{"id":"{}","description":"a","work_time":25,"break_time":5,"created_at":"2024-05-01T10:00:00Z","work_expired_at":"2024-05-01T10:25:00Z","break_expired_at":"2024-05-01T10:30:00Z"}}"#,
+                id
+            );
+            db.insert(id.as_bytes(), value.as_bytes()).unwrap();
+            db.flush().unwrap();
+        }
+
+        let store = SledStore::new(&path).unwrap();
+        let value = store.db.get(id.as_bytes()).unwrap().unwrap();
+        let stored: serde_json::Value = serde_json::from_slice(&value).unwrap();
+        assert_eq!(stored["work_seconds"], 25 * 60);
+        assert_eq!(stored["break_seconds"], 5 * 60);
+        assert!(stored.get("work_time").is_none());
+
+        let notification = store.get(id).unwrap().unwrap();
+        assert_eq!(notification.get_start_at(), notification.created_at);
+        assert_eq!(store.migrate_durations().unwrap(), 0);
+
+        drop(store);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_tasks() {
+        let store = get_temporary_store();
+        assert!(store.list_tasks().unwrap().is_empty());
+
+        let first = store
+            .add_task(String::from("write report"), Some(3))
+            .unwrap();
+        let second = store.add_task(String::from("review"), None).unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+
+        let mut task = store.get_task(1).unwrap().unwrap();
+        assert_eq!(task, first);
+        task.done_at = Some(Utc::now());
+        store.update_task(&task).unwrap();
+
+        let tasks = store.list_tasks().unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks[0].is_done());
+        assert_eq!(tasks[1].title, "review");
+        assert!(store.get_task(3).unwrap().is_none());
+        // tasks live apart from the notifications
+        assert!(store.list_all_notifications().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_record_interruption() {
+        let store = get_temporary_store();
+        let now = Utc::now();
+        let interruption = |kind| Interruption {
+            at: now,
+            kind,
+            note: Some(String::from("phone")),
+        };
+        assert_eq!(
+            store
+                .record_interruption(interruption(InterruptionKind::Internal))
+                .unwrap(),
+            None
+        );
+
+        let on_break = NotificationSled::new(
+            String::from("a"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(27),
+        );
+        let working = NotificationSled::new(
+            String::from("b"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(10),
+        );
+        store.create_notification(&on_break).unwrap();
+        store.create_notification(&working).unwrap();
+
+        for kind in [InterruptionKind::Internal, InterruptionKind::External] {
+            let id = store.record_interruption(interruption(kind)).unwrap();
+            assert_eq!(id, Some(working.id));
+        }
+
+        let stored = store.get(working.id).unwrap().unwrap();
+        assert_eq!(stored.count_interruptions(InterruptionKind::Internal), 1);
+        assert_eq!(stored.count_interruptions(InterruptionKind::External), 1);
+        assert!(store
+            .get(on_break.id)
+            .unwrap()
+            .unwrap()
+            .interruptions
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_notifications() {
+        let store = get_temporary_store();
+        let notification = NotificationSled::new(String::from("a"), 25 * 60, 5 * 60, Utc::now());
+        store.create_notification(&notification).unwrap();
+        let id = notification.id.to_string();
+
+        let found = store.find_notifications(&id[..8]).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, notification.id);
+        let found = store.find_notifications(&id.to_uppercase()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(store.find_notifications("").unwrap().len(), 1);
+        assert!(store.find_notifications("xyz").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clear_history() {
+        let store = get_temporary_store();
+        let now = Utc::now();
+        let archived = |description: &str| {
+            NotificationSled::new(
+                String::from(description),
+                25 * 60,
+                5 * 60,
+                now - Duration::minutes(60),
+            )
+        };
+        let mut tagged = archived("a");
+        tagged.tags = vec![String::from("client")];
+        let running = NotificationSled::new(String::from("c"), 25 * 60, 5 * 60, now);
+        for notification in [&tagged, &archived("b"), &running] {
+            store.create_notification(notification).unwrap();
+        }
+
+        assert_eq!(store.clear_history(Some("client"), None).unwrap(), 1);
+        assert_eq!(store.list_all_notifications().unwrap().len(), 2);
+
+        // the running one stays, nothing is left in the history
+        assert_eq!(store.clear_history(None, None).unwrap(), 1);
+        let notifications = store.list_all_notifications().unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].id, running.id);
+        assert_eq!(store.clear_history(None, None).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_labels() {
+        let mut notification = NotificationSled::new(String::from("a"), 25 * 60, 0, Utc::now());
+        assert!(notification.has_labels(None, None));
+        assert!(!notification.has_labels(Some("client"), None));
+        assert_eq!(notification.get_labels(), None);
+
+        notification.project = Some(String::from("billing"));
+        notification.tags = vec![String::from("client"), String::from("review")];
+        assert!(notification.has_labels(Some("review"), Some("billing")));
+        assert!(!notification.has_labels(Some("review"), Some("ops")));
+        assert!(!notification.has_labels(Some("bill"), None));
+        assert_eq!(
+            notification.get_labels().as_deref(),
+            Some("billing #client #review")
+        );
+    }
+
+    #[test]
+    fn test_sub_minute_durations() {
+        let start_at = Utc::now();
+        let notification = NotificationSled::new(String::from("a"), 90, 30, start_at);
+        assert_eq!(
+            notification.work_expired_at - start_at,
+            Duration::seconds(90)
+        );
+        assert_eq!(
+            notification.break_expired_at - start_at,
+            Duration::seconds(120)
+        );
+    }
+
+    #[test]
+    fn test_open_locked_database() {
+        let path = std::env::temp_dir().join(format!("pomodoro-sled-test-{}", std::process::id()));
+        let store = SledStore::new(&path).unwrap();
+        assert!(matches!(
+            SledStore::new(&path),
+            Err(StoreError::OpenFailed(_, _))
+        ));
+
+        drop(store);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    proptest! {
+        #[test]
+        fn test_decode_arbitrary_value(
+            key in proptest::collection::vec(any::<u8>(), 0..32),
+            value in proptest::collection::vec(any::<u8>(), 0..512),
+        ) {
+            let _ = decode_notification(&key, &value);
+        }
+
+        #[test]
+        fn test_notification_round_trip(
+            description in ".*",
+            work_seconds in 0..u32::MAX / 2,
+            break_seconds in 0..u32::MAX / 2,
+        ) {
+            let notification = NotificationSled::new(description, work_seconds, break_seconds, Utc::now());
+            let value = serde_json::to_vec(&notification).unwrap();
+            let decoded = decode_notification(notification.id.as_bytes(), &value).unwrap();
+            prop_assert_eq!(decoded.id, notification.id);
+            prop_assert_eq!(decoded.description, notification.description);
+            prop_assert_eq!(decoded.break_expired_at, notification.break_expired_at);
+        }
+    }
+}
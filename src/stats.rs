@@ -0,0 +1,362 @@
+use chrono::{prelude::*, Days, Months};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tabled::object::Segment;
+use tabled::style::HorizontalLine;
+use tabled::{Alignment, Modify, Style, Table, Tabled};
+
+use crate::sled_databbase::NotificationSled;
+
+/// Local days a report covers, both ends included.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StatsRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl StatsRange {
+    pub fn day(date: NaiveDate) -> Self {
+        StatsRange {
+            from: date,
+            to: date,
+        }
+    }
+
+    // week runs from monday to sunday
+    pub fn week(date: NaiveDate) -> Self {
+        let from = date - Days::new(date.weekday().num_days_from_monday() as u64);
+        StatsRange {
+            from,
+            to: from + Days::new(6),
+        }
+    }
+
+    pub fn month(date: NaiveDate) -> Self {
+        let from = date.with_day(1).unwrap_or(date);
+        let to = (from + Months::new(1)) - Days::new(1);
+        StatsRange { from, to }
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let date = at.with_timezone(&Local).date_naive();
+        self.from <= date && date <= self.to
+    }
+}
+
+/// Completed pomodoros grouped by a tag or a description.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Tabled)]
+pub struct Breakdown {
+    pub name: String,
+    pub completed: usize,
+    pub focus_minutes: u64,
+}
+
+/// Summary of the pomodoros which started in a range.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Stats {
+    #[serde(flatten)]
+    pub range: StatsRange,
+    pub completed: usize,
+    pub focus_minutes: u64,
+    // average work length of the completed pomodoros
+    pub average_minutes: f64,
+    // share of the finished breaks which were taken in full, none without breaks
+    pub break_adherence: Option<f64>,
+    pub interruptions: usize,
+    pub average_rating: Option<f64>,
+    pub by_tag: Vec<Breakdown>,
+    pub by_description: Vec<Breakdown>,
+}
+
+#[derive(Tabled)]
+struct Metric {
+    metric: &'static str,
+    value: String,
+}
+
+impl Stats {
+    pub fn new(notifications: &[NotificationSled], range: StatsRange, now: DateTime<Utc>) -> Self {
+        let completed = notifications
+            .iter()
+            .filter(|n| n.is_work_done(now) && range.contains(n.get_start_at()))
+            .collect::<Vec<&NotificationSled>>();
+
+        let focus_seconds = completed.iter().map(|n| n.work_seconds as u64).sum::<u64>();
+        let average_minutes = match completed.len() {
+            0 => 0.0,
+            count => focus_seconds as f64 / 60.0 / count as f64,
+        };
+
+        // a break counts as taken when it was planned and no work started before its end
+        let finished_breaks = completed
+            .iter()
+            .filter(|n| n.break_expired_at <= n.get_clock(now))
+            .collect::<Vec<&&NotificationSled>>();
+        let taken_breaks = finished_breaks
+            .iter()
+            .filter(|n| {
+                n.break_seconds > 0
+                    && !notifications.iter().any(|other| {
+                        let start_at = other.get_start_at();
+                        other.id != n.id
+                            && (other.is_work_done(now) || other.is_working(now))
+                            && n.work_expired_at <= start_at
+                            && start_at < n.break_expired_at
+                    })
+            })
+            .count();
+        let break_adherence = match finished_breaks.len() {
+            0 => None,
+            count => Some(taken_breaks as f64 / count as f64),
+        };
+
+        let interruptions = completed.iter().map(|n| n.interruptions.len()).sum();
+
+        let ratings = completed
+            .iter()
+            .filter_map(|n| n.rating)
+            .collect::<Vec<u8>>();
+        let average_rating = match ratings.len() {
+            0 => None,
+            count => Some(ratings.iter().map(|r| *r as f64).sum::<f64>() / count as f64),
+        };
+
+        let by_tag = get_breakdowns(completed.iter().flat_map(|n| {
+            n.tags
+                .iter()
+                .map(move |tag| (tag.to_string(), n.work_seconds))
+        }));
+        let by_description = get_breakdowns(
+            completed
+                .iter()
+                .map(|n| (n.description.to_string(), n.work_seconds)),
+        );
+
+        Stats {
+            range,
+            completed: completed.len(),
+            focus_minutes: to_minutes(focus_seconds),
+            average_minutes,
+            break_adherence,
+            interruptions,
+            average_rating,
+            by_tag,
+            by_description,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let range = match self.range.from == self.range.to {
+            true => self.range.from.to_string(),
+            false => format!("{} ~ {}", self.range.from, self.range.to),
+        };
+        let percentage = |ratio: Option<f64>| {
+            ratio
+                .map(|ratio| format!("{:.0}%", ratio * 100.0))
+                .unwrap_or_else(|| String::from("-"))
+        };
+
+        let metrics = vec![
+            Metric {
+                metric: "range",
+                value: range,
+            },
+            Metric {
+                metric: "completed",
+                value: self.completed.to_string(),
+            },
+            Metric {
+                metric: "focus minutes",
+                value: self.focus_minutes.to_string(),
+            },
+            Metric {
+                metric: "average minutes",
+                value: format!("{:.1}", self.average_minutes),
+            },
+            Metric {
+                metric: "break adherence",
+                value: percentage(self.break_adherence),
+            },
+            Metric {
+                metric: "interruptions",
+                value: self.interruptions.to_string(),
+            },
+            Metric {
+                metric: "average focus",
+                value: self
+                    .average_rating
+                    .map(|rating| format!("{:.1}", rating))
+                    .unwrap_or_else(|| String::from("-")),
+            },
+        ];
+
+        let mut tables = vec![style(Table::new(metrics))];
+        for (name, breakdowns) in [("tag", &self.by_tag), ("description", &self.by_description)] {
+            if breakdowns.is_empty() {
+                continue;
+            }
+
+            let mut builder = Table::builder(breakdowns);
+            builder.set_columns([name, "completed", "focus_minutes"]);
+            tables.push(style(builder.build()));
+        }
+
+        tables.join("\n")
+    }
+}
+
+fn style(mut table: Table) -> String {
+    table
+        .with(
+            Style::modern()
+                .off_horizontal()
+                .horizontals([HorizontalLine::new(1, Style::modern().get_horizontal())]),
+        )
+        .with(Modify::new(Segment::all()).with(Alignment::center()))
+        .to_string()
+}
+
+// get_breakdowns sums the work seconds by name, the longest focus first
+fn get_breakdowns(items: impl Iterator<Item = (String, u32)>) -> Vec<Breakdown> {
+    let mut totals: HashMap<String, (usize, u64)> = HashMap::new();
+    for (name, work_seconds) in items {
+        let total = totals.entry(name).or_default();
+        total.0 += 1;
+        total.1 += work_seconds as u64;
+    }
+
+    let mut totals = totals.into_iter().collect::<Vec<(String, (usize, u64))>>();
+    totals.sort_by(|(a, (_, a_seconds)), (b, (_, b_seconds))| {
+        b_seconds.cmp(a_seconds).then_with(|| a.cmp(b))
+    });
+
+    totals
+        .into_iter()
+        .map(|(name, (completed, focus_seconds))| Breakdown {
+            name,
+            completed,
+            focus_minutes: to_minutes(focus_seconds),
+        })
+        .collect()
+}
+
+fn to_minutes(seconds: u64) -> u64 {
+    (seconds + 30) / 60
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, Duration};
+
+    use super::{Stats, StatsRange};
+    use crate::sled_databbase::{NotificationOutcome, NotificationSled};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    // started at the given local time of March 2024
+    fn started(
+        description: &str,
+        day: u32,
+        hour: u32,
+        work: u32,
+        r#break: u32,
+    ) -> NotificationSled {
+        let start_at = Local
+            .from_local_datetime(&date(day).and_hms_opt(hour, 0, 0).unwrap())
+            .unwrap()
+            .with_timezone(&Utc);
+        NotificationSled::new(String::from(description), work * 60, r#break * 60, start_at)
+    }
+
+    #[test]
+    fn test_stats_range() {
+        // 2024-03-06 is a wednesday
+        assert_eq!(
+            StatsRange::day(date(6)),
+            StatsRange {
+                from: date(6),
+                to: date(6)
+            }
+        );
+        assert_eq!(
+            StatsRange::week(date(6)),
+            StatsRange {
+                from: date(4),
+                to: date(10)
+            }
+        );
+        assert_eq!(StatsRange::week(date(4)), StatsRange::week(date(10)));
+        assert_eq!(
+            StatsRange::month(date(6)),
+            StatsRange {
+                from: date(1),
+                to: date(31)
+            }
+        );
+        let february = NaiveDate::from_ymd_opt(2024, 2, 10).unwrap();
+        assert_eq!(
+            StatsRange::month(february).to,
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut notifications = vec![
+            started("write", 5, 9, 25, 5),
+            // starts during the break of the first one
+            started("write", 5, 9, 25, 5),
+            started("review", 5, 14, 50, 10),
+            started("plan", 5, 16, 15, 0),
+            started("missed", 5, 18, 25, 5),
+            started("write", 6, 9, 25, 5),
+        ];
+        notifications[1].start_at = Some(notifications[0].work_expired_at + Duration::minutes(2));
+        notifications[1].work_expired_at =
+            notifications[1].start_at.unwrap() + Duration::minutes(25);
+        notifications[1].break_expired_at = notifications[1].work_expired_at + Duration::minutes(5);
+        notifications[0].tags = vec![String::from("client")];
+        notifications[2].tags = vec![String::from("client"), String::from("deep")];
+        notifications[0].rating = Some(4);
+        notifications[2].rating = Some(5);
+        notifications[4].outcome = Some(NotificationOutcome::Missed);
+        let now = notifications[5].break_expired_at;
+
+        let stats = Stats::new(&notifications, StatsRange::day(date(5)), now);
+        assert_eq!(stats.completed, 4);
+        assert_eq!(stats.focus_minutes, 115);
+        assert_eq!(stats.average_minutes, 28.75);
+        // the first break was cut short and the last pomodoro had none
+        assert_eq!(stats.break_adherence, Some(0.5));
+        assert_eq!(stats.average_rating, Some(4.5));
+
+        let tags = stats
+            .by_tag
+            .iter()
+            .map(|b| (b.name.as_str(), b.completed, b.focus_minutes))
+            .collect::<Vec<_>>();
+        assert_eq!(tags, vec![("client", 2, 75), ("deep", 1, 50)]);
+        let descriptions = stats
+            .by_description
+            .iter()
+            .map(|b| (b.name.as_str(), b.completed, b.focus_minutes))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            descriptions,
+            vec![("review", 1, 50), ("write", 2, 50), ("plan", 1, 15)]
+        );
+
+        let stats = Stats::new(&notifications, StatsRange::week(date(5)), now);
+        assert_eq!(stats.completed, 5);
+        assert!(stats.render().contains("description"));
+
+        let stats = Stats::new(&notifications, StatsRange::day(date(7)), now);
+        assert_eq!(stats.completed, 0);
+        assert_eq!(stats.break_adherence, None);
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["from"], "2024-03-07");
+        assert_eq!(json["completed"], 0);
+    }
+}
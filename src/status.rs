@@ -0,0 +1,317 @@
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::goal::GoalProgress;
+use crate::sled_databbase::NotificationSled;
+
+pub const DEFAULT_STATUS_TEMPLATE: &str = "{phase} {remaining} {description}";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Idle,
+    Work,
+    Break,
+    Paused,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Idle => "idle",
+            Phase::Work => "work",
+            Phase::Break => "break",
+            Phase::Paused => "paused",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StatusFormat {
+    Json,
+    Plain,
+    Template(String),
+}
+
+/// Snapshot of the running pomodoro, cheap enough to be polled every second
+/// by status bars (waybar, polybar, tmux, ...).
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Status {
+    pub phase: Phase,
+    pub id: Option<Uuid>,
+    pub remaining_seconds: u64,
+    pub description: Option<String>,
+    // pomodoros fully finished (work and break) during the current local day, missed ones excluded
+    pub completed: usize,
+    pub queued: usize,
+    // interruptions recorded during the current local day
+    #[serde(default)]
+    pub interruptions: usize,
+    // progress of the current local day, set when a daily goal is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<GoalProgress>,
+}
+
+impl Status {
+    pub fn new(notifications: &[NotificationSled], now: DateTime<Utc>) -> Self {
+        let today = now.with_timezone(&Local).date_naive();
+
+        let completed = notifications
+            .iter()
+            .filter(|n| {
+                let finished_at = n.work_expired_at.max(n.break_expired_at);
+                n.is_work_done(now)
+                    && !n.is_paused()
+                    && finished_at <= now
+                    && finished_at.with_timezone(&Local).date_naive() == today
+            })
+            .count();
+
+        let interruptions = notifications
+            .iter()
+            .flat_map(|n| n.interruptions.iter())
+            .filter(|i| i.at.with_timezone(&Local).date_naive() == today)
+            .count();
+
+        let queued = notifications
+            .iter()
+            .filter(|n| n.get_start_at() > now)
+            .count();
+
+        // notifications can overlap when created with `create`, pick the running one
+        // which reaches its next phase change first, paused ones come after
+        let current = notifications
+            .iter()
+            .filter(|n| {
+                let clock = n.get_clock(now);
+                n.get_start_at() <= clock && n.work_expired_at.max(n.break_expired_at) > clock
+            })
+            .map(|n| {
+                let clock = n.get_clock(now);
+                let (phase, expired_at) = if n.work_seconds > 0 && n.work_expired_at > clock {
+                    (Phase::Work, n.work_expired_at)
+                } else {
+                    (Phase::Break, n.break_expired_at)
+                };
+                let phase = if n.is_paused() { Phase::Paused } else { phase };
+
+                (n, phase, expired_at - clock)
+            })
+            .min_by_key(|(n, _, remaining)| (n.is_paused(), *remaining));
+
+        match current {
+            Some((notification, phase, remaining)) => Status {
+                phase,
+                id: Some(notification.get_id()),
+                remaining_seconds: remaining.max(Duration::zero()).num_seconds() as u64,
+                description: Some(notification.description.clone()),
+                completed,
+                queued,
+                interruptions,
+                goal: None,
+            },
+            None => Status {
+                phase: Phase::Idle,
+                id: None,
+                remaining_seconds: 0,
+                description: None,
+                completed,
+                queued,
+                interruptions,
+                goal: None,
+            },
+        }
+    }
+
+    pub fn from_json(s: &str) -> Option<Self> {
+        serde_json::from_str(s).ok()
+    }
+
+    pub fn to_json(&self) -> String {
+        // serializing plain fields can not fail
+        serde_json::to_string(self).unwrap_or_else(|_| String::from("{}"))
+    }
+
+    pub fn get_remaining(&self) -> String {
+        format!(
+            "{:02}:{:02}",
+            self.remaining_seconds / 60,
+            self.remaining_seconds % 60
+        )
+    }
+
+    pub fn render(&self, format: &StatusFormat) -> String {
+        match format {
+            StatusFormat::Json => self.to_json(),
+            StatusFormat::Plain => match self.phase {
+                Phase::Idle => String::from(Phase::Idle.as_str()),
+                _ => self.render_template(DEFAULT_STATUS_TEMPLATE),
+            },
+            StatusFormat::Template(template) => self.render_template(template),
+        }
+    }
+
+    fn render_template(&self, template: &str) -> String {
+        template
+            .replace("{phase}", self.phase.as_str())
+            .replace("{remaining_seconds}", &self.remaining_seconds.to_string())
+            .replace("{remaining}", &self.get_remaining())
+            .replace(
+                "{description}",
+                self.description.as_deref().unwrap_or_default(),
+            )
+            .replace(
+                "{id}",
+                &self.id.map(|id| id.to_string()).unwrap_or_default(),
+            )
+            .replace("{completed}", &self.completed.to_string())
+            .replace("{queued}", &self.queued.to_string())
+            .replace("{interruptions}", &self.interruptions.to_string())
+            .replace(
+                "{goal}",
+                &self
+                    .goal
+                    .map(|progress| progress.get_short())
+                    .unwrap_or_default(),
+            )
+            .trim()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{prelude::*, Duration};
+
+    use super::{Phase, Status, StatusFormat};
+    use crate::goal::{DailyGoal, GoalProgress};
+    use crate::sled_databbase::{
+        Interruption, InterruptionKind, NotificationOutcome, NotificationSled,
+    };
+
+    #[test]
+    fn test_status_idle() {
+        let status = Status::new(&[], Utc::now());
+
+        assert_eq!(status.phase, Phase::Idle);
+        assert_eq!(status.remaining_seconds, 0);
+        assert_eq!(status.render(&StatusFormat::Plain), "idle");
+    }
+
+    #[test]
+    fn test_status_phases() {
+        let now = Utc::now();
+        let notifications = vec![
+            // currently in work phase, 10 minutes left
+            NotificationSled::new(
+                String::from("write"),
+                25 * 60,
+                5 * 60,
+                now - Duration::minutes(15),
+            ),
+            // starts after the current one
+            NotificationSled::new(
+                String::from("next"),
+                25 * 60,
+                5 * 60,
+                now + Duration::minutes(15),
+            ),
+        ];
+
+        let status = Status::new(&notifications, now);
+        assert_eq!(status.phase, Phase::Work);
+        assert_eq!(status.remaining_seconds, 600);
+        assert_eq!(status.description.as_deref(), Some("write"));
+        assert_eq!(status.queued, 1);
+        assert_eq!(status.render(&StatusFormat::Plain), "work 10:00 write");
+
+        let notifications = vec![NotificationSled::new(
+            String::from("rest"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(27),
+        )];
+        let status = Status::new(&notifications, now);
+        assert_eq!(status.phase, Phase::Break);
+        assert_eq!(status.remaining_seconds, 180);
+
+        // a pomodoro queued while the server was down is not counted as completed
+        let now = Local
+            .with_ymd_and_hms(2024, 3, 20, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut notifications = [10, 40].map(|minutes_ago| {
+            NotificationSled::new(
+                String::from("done"),
+                60,
+                60,
+                now - Duration::minutes(minutes_ago),
+            )
+        });
+        notifications[1].outcome = Some(NotificationOutcome::Missed);
+        assert_eq!(Status::new(&notifications, now).completed, 1);
+    }
+
+    #[test]
+    fn test_status_paused() {
+        let now = Utc::now();
+        let mut paused = NotificationSled::new(
+            String::from("paused"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(40),
+        );
+        // paused 10 minutes into the work time, 30 minutes ago
+        paused.paused_at = Some(now - Duration::minutes(30));
+
+        let status = Status::new(std::slice::from_ref(&paused), now);
+        assert_eq!(status.phase, Phase::Paused);
+        assert_eq!(status.remaining_seconds, 15 * 60);
+        assert_eq!(status.completed, 0);
+        assert_eq!(status.render(&StatusFormat::Plain), "paused 15:00 paused");
+
+        // a running pomodoro comes first
+        let running = NotificationSled::new(
+            String::from("running"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(1),
+        );
+        let status = Status::new(&[paused, running], now);
+        assert_eq!(status.phase, Phase::Work);
+        assert_eq!(status.description.as_deref(), Some("running"));
+    }
+
+    #[test]
+    fn test_status_render() {
+        let now = Utc::now();
+        let mut notification = NotificationSled::new(
+            String::from("write"),
+            25 * 60,
+            5 * 60,
+            now - Duration::minutes(5),
+        );
+        notification.interruptions.push(Interruption {
+            at: now,
+            kind: InterruptionKind::External,
+            note: None,
+        });
+        let mut status = Status::new(&[notification], now);
+
+        let rendered = status.render(&StatusFormat::Template(String::from(
+            "[{phase}] {remaining_seconds}s queued:{queued} interruptions:{interruptions} {goal}",
+        )));
+        assert_eq!(rendered, "[work] 1200s queued:0 interruptions:1");
+
+        status.goal = Some(GoalProgress {
+            goal: DailyGoal::Pomodoros(8),
+            done: 3,
+        });
+        let rendered = status.render(&StatusFormat::Template(String::from("{phase} {goal}")));
+        assert_eq!(rendered, "work 3/8");
+
+        let json = status.render(&StatusFormat::Json);
+        assert_eq!(Status::from_json(&json), Some(status));
+    }
+}
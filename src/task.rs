@@ -0,0 +1,147 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use tabled::Tabled;
+
+use crate::sled_databbase::NotificationSled;
+
+/// A planned piece of work, pomodoros are linked to it with `create --task <id>`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Task {
+    pub id: u64,
+    pub title: String,
+    // estimated number of pomodoros
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<u16>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub done_at: Option<DateTime<Utc>>,
+}
+
+impl Task {
+    pub fn new(id: u64, title: String, estimate: Option<u16>) -> Self {
+        Task {
+            id,
+            title,
+            estimate,
+            created_at: Utc::now(),
+            done_at: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done_at.is_some()
+    }
+}
+
+/// A task with the number of pomodoros actually spent on it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TaskProgress {
+    #[serde(flatten)]
+    pub task: Task,
+    pub actual: usize,
+}
+
+impl TaskProgress {
+    pub fn new(task: Task, notifications: &[NotificationSled], now: DateTime<Utc>) -> Self {
+        let actual = count_pomodoros(task.id, notifications, now);
+        TaskProgress { task, actual }
+    }
+}
+
+// count_pomodoros counts the linked pomodoros whose work time is over
+pub fn count_pomodoros(
+    task_id: u64,
+    notifications: &[NotificationSled],
+    now: DateTime<Utc>,
+) -> usize {
+    notifications
+        .iter()
+        .filter(|n| n.task == Some(task_id) && n.is_work_done(now))
+        .count()
+}
+
+impl Tabled for TaskProgress {
+    const LENGTH: usize = 5;
+
+    fn fields(&self) -> Vec<Cow<'_, str>> {
+        let estimate = self
+            .task
+            .estimate
+            .map(|estimate| estimate.to_string())
+            .unwrap_or_else(|| String::from("-"));
+
+        let done_at = match self.task.done_at {
+            Some(done_at) => {
+                let local_time: DateTime<Local> = done_at.into();
+                local_time.format("%F %T %z").to_string()
+            }
+            None => String::from("-"),
+        };
+
+        vec![
+            self.task.id.to_string(),
+            self.task.title.to_string(),
+            estimate,
+            self.actual.to_string(),
+            done_at,
+        ]
+        .into_iter()
+        .map(|x| x.into())
+        .collect()
+    }
+
+    fn headers() -> Vec<Cow<'static, str>> {
+        vec!["id", "title", "estimate", "actual", "done_at"]
+            .into_iter()
+            .map(|x| x.to_string().into())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::{count_pomodoros, Task, TaskProgress};
+    use crate::sled_databbase::{NotificationOutcome, NotificationSled};
+
+    #[test]
+    fn test_count_pomodoros() {
+        let now = Utc::now();
+        let started = |description: &str, minutes_ago: i64| {
+            NotificationSled::new(
+                String::from(description),
+                25 * 60,
+                5 * 60,
+                now - Duration::minutes(minutes_ago),
+            )
+        };
+        let mut notifications = vec![
+            started("a", 60),
+            started("b", 27),
+            started("c", 10),
+            started("d", 90),
+            started("e", 90),
+        ];
+        for notification in notifications.iter_mut().take(4) {
+            notification.task = Some(1);
+        }
+        notifications[3].outcome = Some(NotificationOutcome::Missed);
+
+        // a and b are done with work, c is still running, d was missed, e is not linked
+        assert_eq!(count_pomodoros(1, &notifications, now), 2);
+        assert_eq!(count_pomodoros(2, &notifications, now), 0);
+
+        let progress = TaskProgress::new(
+            Task::new(1, String::from("write report"), Some(3)),
+            &notifications,
+            now,
+        );
+        assert_eq!(progress.actual, 2);
+        let json = serde_json::to_value(&progress).unwrap();
+        assert_eq!(json["title"], "write report");
+        assert_eq!(json["estimate"], 3);
+        assert_eq!(json["actual"], 2);
+    }
+}